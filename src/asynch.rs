@@ -0,0 +1,874 @@
+//! Async twin of [`crate::IS31FL3731`] built on `embedded-hal-async`.
+//!
+//! It offers the same operations as the blocking driver. The SDB pin stays a blocking
+//! [`OutputPin`], since `embedded-hal-async` has no async output pin, and
+//! [`IS31FL3731::wait_for_movie_end`] awaits INTB instead of polling it, leaving timeouts to the
+//! executor.
+//!
+//! [`crate::FrameBuffer::flush_async`] uploads a frame buffer through this driver.
+//! [`crate::DoubleBuffer`], [`crate::TiledDisplay`] and [`crate::Marquee`] only drive the blocking
+//! driver; with this one, draw the back frame and show it with [`IS31FL3731::display_frame`].
+
+use core::time::Duration;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::I2c;
+
+use crate::{
+    blink_period_units, check_blink_period, check_frame, check_function_register, check_max_burst,
+    control_bit, control_row, font, pwm_bursts, pwm_register, update_bits, with_blink_period,
+    Address, AudioPlay, AutoPlay, Breath, BreathSteps, Error, Frame, FrameState, Layout, Linear,
+    NoPin, RgbLayout, ISSI_BANK_FUNCTION_REGISTER, ISSI_COMMAND_REGISTER,
+    ISSI_DISPLAY_OPTION_BLINK_ENABLE, ISSI_LED_CONTROL_LEN, ISSI_PWM_LEN, ISSI_REG_AUDIOSYNC,
+    ISSI_REG_AUTOPLAY_CONTROL1, ISSI_REG_BLINK_CONTROL, ISSI_REG_BREATH_CONTROL1, ISSI_REG_CONFIG,
+    ISSI_REG_CONFIG_AUDIOPLAY_MODE, ISSI_REG_CONFIG_AUTOPLAY_MODE, ISSI_REG_CONFIG_PICTURE_MODE,
+    ISSI_REG_DISPLAY_OPTION, ISSI_REG_FRAME_STATE, ISSI_REG_LED_CONTROL, ISSI_REG_PICTURE_FRAME,
+    ISSI_REG_PWM, ISSI_REG_SHUTDOWN, SDB_WAKE_DELAY_MS,
+};
+
+pub struct IS31FL3731<I2C, Delay, Sdb = NoPin, L = Linear>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin
+{
+    i2c: I2C,
+    delay: Delay,
+    sdb: Sdb,
+    layout: L,
+    /// Bank last written to the command register, `None` when unknown.
+    bank: Option<u8>,
+    max_burst: usize,
+    address: Address,
+}
+
+impl<I2C, Delay> IS31FL3731<I2C, Delay>
+where
+    I2C: I2c,
+    Delay: DelayNs {
+    pub fn new(i2c: I2C, delay: Delay, address: Address) -> Self {
+        Self::with_sdb(i2c, delay, address, NoPin)
+    }
+}
+
+impl<I2C, Delay, Sdb> IS31FL3731<I2C, Delay, Sdb>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin {
+    /// See [`crate::IS31FL3731::with_sdb`].
+    pub fn with_sdb(i2c: I2C, delay: Delay, address: Address, sdb: Sdb) -> Self {
+        Self {
+            i2c,
+            delay,
+            sdb,
+            layout: Linear,
+            bank: None,
+            max_burst: ISSI_PWM_LEN,
+            address,
+        }
+    }
+}
+
+impl<I2C, Delay, Sdb, L, I2cError> IS31FL3731<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin {
+    /// Replaces the pixel mapping used by [`Self::set_pixel`].
+    pub fn with_layout<L2>(self, layout: L2) -> IS31FL3731<I2C, Delay, Sdb, L2> {
+        IS31FL3731 {
+            i2c: self.i2c,
+            delay: self.delay,
+            sdb: self.sdb,
+            layout,
+            bank: self.bank,
+            max_burst: self.max_burst,
+            address: self.address,
+        }
+    }
+
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
+    /// Gives back the bus and delay. An SDB pin is dropped; see [`Self::release_with_sdb`].
+    pub fn release(self) -> (I2C, Delay) {
+        (self.i2c, self.delay)
+    }
+
+    pub fn release_with_sdb(self) -> (I2C, Delay, Sdb) {
+        (self.i2c, self.delay, self.sdb)
+    }
+
+    /// See [`crate::IS31FL3731::power_up`].
+    pub async fn power_up(&mut self) -> Result<(), Error<I2cError>> {
+        self.sdb.set_high().map_err(|_| Error::Pin)?;
+        self.delay.delay_ms(SDB_WAKE_DELAY_MS).await;
+
+        Ok(())
+    }
+
+    /// See [`crate::IS31FL3731::power_down`].
+    pub fn power_down(&mut self) -> Result<(), Error<I2cError>> {
+        self.sdb.set_low().map_err(|_| Error::Pin)?;
+        self.bank = None;
+
+        Ok(())
+    }
+
+    /// See [`crate::IS31FL3731::hard_reset`].
+    pub async fn hard_reset(&mut self) -> Result<(), Error<I2cError>> {
+        self.power_down()?;
+        self.delay.delay_ms(10).await;
+        self.power_up().await?;
+        self.reset().await
+    }
+
+    pub async fn reset(&mut self) -> Result<(), Error<I2cError>> {
+        self.shutdown().await?;
+        self.delay.delay_ms(10).await;
+        self.wake().await?;
+        self.picture_mode().await?;
+
+        Ok(())
+    }
+
+    pub async fn shutdown(&mut self) -> Result<(), Error<I2cError>> {
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_SHUTDOWN, 0x00).await
+    }
+
+    pub async fn wake(&mut self) -> Result<(), Error<I2cError>> {
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_SHUTDOWN, 0x01).await
+    }
+
+    pub async fn picture_mode(&mut self) -> Result<(), Error<I2cError>> {
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE).await
+    }
+
+    pub async fn display_frame(&mut self, frame: Frame) -> Result<(), Error<I2cError>> {
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_PICTURE_FRAME, frame.index()).await
+    }
+
+    pub async fn frame_state(&mut self) -> Result<FrameState, Error<I2cError>> {
+        let data = self.read_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_FRAME_STATE).await?;
+
+        Ok(FrameState::decode(data))
+    }
+
+    pub async fn clear_interrupt(&mut self) -> Result<(), Error<I2cError>> {
+        self.frame_state().await?;
+
+        Ok(())
+    }
+
+    /// Awaits INTB going low, then reads and clears the frame state.
+    pub async fn wait_for_movie_end<Intb: Wait>(&mut self, intb: &mut Intb) -> Result<FrameState, Error<I2cError>> {
+        intb.wait_for_low().await.map_err(|_| Error::Pin)?;
+
+        self.frame_state().await
+    }
+
+    pub async fn auto_play(&mut self, config: &AutoPlay) -> Result<Duration, Error<I2cError>> {
+        let (control1, control2, frame_delay) = config.encode()?;
+
+        self.write_registers(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_AUTOPLAY_CONTROL1, &[control1, control2]).await?;
+        self.write(&[ISSI_REG_CONFIG, ISSI_REG_CONFIG_AUTOPLAY_MODE | config.start_frame.index()]).await?;
+
+        Ok(frame_delay)
+    }
+
+    pub async fn audio_play(&mut self, config: &AudioPlay) -> Result<(), Error<I2cError>> {
+        let control1 = config.encode()?;
+
+        self.write_registers(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_AUTOPLAY_CONTROL1, &[control1]).await?;
+        self.write(&[ISSI_REG_AUDIOSYNC, config.audio_sync as u8]).await?;
+        self.write(&[ISSI_REG_CONFIG, ISSI_REG_CONFIG_AUDIOPLAY_MODE | config.start_frame.index()]).await?;
+
+        Ok(())
+    }
+
+    pub async fn breath(&mut self, config: &Breath) -> Result<BreathSteps, Error<I2cError>> {
+        let (control1, control2, steps) = config.encode();
+        self.write_registers(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_BREATH_CONTROL1, &[control1, control2]).await?;
+
+        Ok(steps)
+    }
+
+    pub async fn audio_sync(&mut self, enable: bool) -> Result<(), Error<I2cError>> {
+        let data = if enable { 1 } else { 0 };
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_AUDIOSYNC, data).await
+    }
+
+    pub async fn clear(&mut self, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        self.select_bank(frame).await?;
+
+        let mut erase_buf = [0; 25];
+        for x in 0..6 {
+            erase_buf[0] = ISSI_REG_PWM + x*24;
+            self.write(&erase_buf).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_pwm_by_index(&mut self, index: usize, pwm: u8, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let reg = pwm_register(index)?;
+        self.write_register(frame, reg, pwm).await
+    }
+
+    /// See [`crate::IS31FL3731::set_max_burst`].
+    pub fn set_max_burst(&mut self, len: usize) -> Result<(), Error<I2cError>> {
+        self.max_burst = check_max_burst(len)?;
+
+        Ok(())
+    }
+
+    pub async fn write_frame(&mut self, frame: impl Into<u8>, pwm: &[u8; ISSI_PWM_LEN]) -> Result<(), Error<I2cError>> {
+        self.write_pwm(frame, 0, pwm).await
+    }
+
+    pub async fn write_pwm(&mut self, frame: impl Into<u8>, index: usize, pwm: &[u8]) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        for (reg, chunk) in pwm_bursts(index, pwm, self.max_burst)? {
+            self.write_registers(frame, reg, chunk).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_led_enabled(&mut self, index: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.update_control_bit(ISSI_REG_LED_CONTROL, index, enable, frame).await
+    }
+
+    pub async fn set_row_enabled(&mut self, row: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.write_control_row(ISSI_REG_LED_CONTROL, row, enable, frame).await
+    }
+
+    pub async fn set_all_leds_enabled(&mut self, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let data = if enable { 0xFF } else { 0x00 };
+        self.write_led_mask(&[data; ISSI_LED_CONTROL_LEN], frame).await
+    }
+
+    pub async fn write_led_mask(&mut self, mask: &[u8; ISSI_LED_CONTROL_LEN], frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        self.write_registers(frame, ISSI_REG_LED_CONTROL, mask).await
+    }
+
+    pub async fn set_led_blink(&mut self, index: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.update_control_bit(ISSI_REG_BLINK_CONTROL, index, enable, frame).await
+    }
+
+    pub async fn set_row_blink(&mut self, row: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.write_control_row(ISSI_REG_BLINK_CONTROL, row, enable, frame).await
+    }
+
+    pub async fn write_blink_mask(&mut self, mask: &[u8; ISSI_LED_CONTROL_LEN], frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        self.write_registers(frame, ISSI_REG_BLINK_CONTROL, mask).await
+    }
+
+    pub async fn blink(&mut self, enable: bool) -> Result<(), Error<I2cError>> {
+        let current = self.read_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_DISPLAY_OPTION).await?;
+        let data = update_bits(current, ISSI_DISPLAY_OPTION_BLINK_ENABLE, enable);
+        self.write(&[ISSI_REG_DISPLAY_OPTION, data]).await
+    }
+
+    /// See [`crate::IS31FL3731::set_blink_period`].
+    pub async fn set_blink_period(&mut self, units: u8) -> Result<(), Error<I2cError>> {
+        check_blink_period(units)?;
+
+        let current = self.read_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_DISPLAY_OPTION).await?;
+        let data = with_blink_period(current, units);
+        self.write(&[ISSI_REG_DISPLAY_OPTION, data]).await
+    }
+
+    /// See [`crate::IS31FL3731::set_blink_period_duration`].
+    pub async fn set_blink_period_duration(&mut self, period: Duration) -> Result<Duration, Error<I2cError>> {
+        let (units, period) = blink_period_units(period)?;
+        self.set_blink_period(units).await?;
+
+        Ok(period)
+    }
+
+    /// See [`crate::IS31FL3731::read_function_register`].
+    pub async fn read_function_register(&mut self, reg: u8) -> Result<u8, Error<I2cError>> {
+        check_function_register(reg)?;
+
+        self.read_register(ISSI_BANK_FUNCTION_REGISTER, reg).await
+    }
+
+    pub async fn read_pwm(&mut self, frame: impl Into<u8>) -> Result<[u8; ISSI_PWM_LEN], Error<I2cError>> {
+        let mut pwm = [0; ISSI_PWM_LEN];
+        let frame = check_frame(frame)?;
+        self.read_registers(frame, ISSI_REG_PWM, &mut pwm).await?;
+
+        Ok(pwm)
+    }
+
+    pub async fn read_led_mask(&mut self, frame: impl Into<u8>) -> Result<[u8; ISSI_LED_CONTROL_LEN], Error<I2cError>> {
+        let mut mask = [0; ISSI_LED_CONTROL_LEN];
+        let frame = check_frame(frame)?;
+        self.read_registers(frame, ISSI_REG_LED_CONTROL, &mut mask).await?;
+
+        Ok(mask)
+    }
+
+    pub async fn read_blink_mask(&mut self, frame: impl Into<u8>) -> Result<[u8; ISSI_LED_CONTROL_LEN], Error<I2cError>> {
+        let mut mask = [0; ISSI_LED_CONTROL_LEN];
+        let frame = check_frame(frame)?;
+        self.read_registers(frame, ISSI_REG_BLINK_CONTROL, &mut mask).await?;
+
+        Ok(mask)
+    }
+
+    async fn update_control_bit(&mut self, base: u8, index: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let (reg, bit) = control_bit(base, index)?;
+        let current = self.read_register(frame, reg).await?;
+        self.write(&[reg, update_bits(current, bit, enable)]).await
+    }
+
+    async fn write_control_row(&mut self, base: u8, row: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let (reg, data) = control_row(base, row, enable)?;
+        self.write_registers(frame, reg, &data).await
+    }
+
+    async fn select_bank(&mut self, bank: u8) -> Result<(), Error<I2cError>> {
+        if self.bank != Some(bank) {
+            self.write(&[ISSI_COMMAND_REGISTER, bank]).await?;
+            self.bank = Some(bank);
+        }
+
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<(), Error<I2cError>> {
+        self.i2c.write(self.address as u8, data).await.inspect_err(|_| self.bank = None)?;
+
+        Ok(())
+    }
+
+    async fn write_register(&mut self, bank: u8, reg: u8, data: u8) -> Result<(), Error<I2cError>> {
+        self.select_bank(bank).await?;
+        self.write(&[reg, data]).await
+    }
+
+    async fn write_registers(&mut self, bank: u8, reg: u8, data: &[u8]) -> Result<(), Error<I2cError>> {
+        let mut buf = [0; ISSI_PWM_LEN + 1];
+        buf[0] = reg;
+        buf[1..=data.len()].copy_from_slice(data);
+
+        self.select_bank(bank).await?;
+        self.write(&buf[..=data.len()]).await
+    }
+
+    async fn read_register(&mut self, bank: u8, reg: u8) -> Result<u8, Error<I2cError>> {
+        let mut data = [0];
+        self.read_registers(bank, reg, &mut data).await?;
+
+        Ok(data[0])
+    }
+
+    async fn read_registers(&mut self, bank: u8, reg: u8, data: &mut [u8]) -> Result<(), Error<I2cError>> {
+        self.select_bank(bank).await?;
+        self.i2c.write_read(self.address as u8, &[reg], data).await.inspect_err(|_| self.bank = None)?;
+
+        Ok(())
+    }
+}
+
+impl<I2C, Delay, Sdb, L, I2cError> IS31FL3731<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin,
+    L: Layout {
+    /// Sets the PWM value of the logical pixel `(x, y)` as mapped by the driver's [`Layout`].
+    pub async fn set_pixel(&mut self, x: usize, y: usize, pwm: u8, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let index = self.layout.index(x, y).ok_or(Error::InvalidCoordinate)?;
+
+        self.set_pwm_by_index(index, pwm, frame).await
+    }
+
+    /// See [`crate::IS31FL3731::draw_text`].
+    pub async fn draw_text(&mut self, frame: impl Into<u8>, x: i32, y: i32, text: &str, brightness: u8) -> Result<usize, Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let (width, height) = (self.layout.width(), self.layout.height());
+
+        for (px, py) in font::pixels(text, x, y, width, height) {
+            self.set_pixel(px, py, brightness, frame).await?;
+        }
+
+        Ok(font::text_width(text))
+    }
+}
+
+impl<I2C, Delay, Sdb, L, I2cError> IS31FL3731<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin,
+    L: RgbLayout {
+    /// Sets the red, green and blue PWM values of the logical pixel `(x, y)` of an RGB board.
+    pub async fn set_pixel_rgb(&mut self, x: usize, y: usize, rgb: [u8; 3], frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = frame.into();
+        let indices = self.layout.rgb_index(x, y).ok_or(Error::InvalidCoordinate)?;
+
+        for (index, pwm) in indices.into_iter().zip(rgb) {
+            self.set_pwm_by_index(index, pwm, frame).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boards, Loops};
+    use crate::test_util::{block_on, vec, DelayStub, Vec};
+    use embedded_hal_mock::eh1::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn test_reset() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x01]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.reset()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_clear() {
+        const FRAME:u8 = 3;
+        let mut transactions = vec![
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, FRAME]),
+        ];
+        for x in 0..6 {
+            let mut erase_buf = vec![0; 25];
+            erase_buf[0] = 36 + x*24;
+            transactions.push(Transaction::write(Address::GND as u8, erase_buf));
+        }
+        let mut i2c = Mock::new(&transactions);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.clear(FRAME)).unwrap();
+        assert_eq!(block_on(sut.clear(8)), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_audio_sync() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 0]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.audio_sync(true)).unwrap();
+        block_on(sut.audio_sync(false)).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pwm_by_index() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 4]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 128]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 5]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 50, 255]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.set_pwm_by_index(0, 128, 4)).unwrap();
+        block_on(sut.set_pwm_by_index(50, 255, 5)).unwrap();
+        assert_eq!(block_on(sut.set_pwm_by_index(144, 255, 5)), Err(Error::IndexOutOfRange));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_pwm_with_max_burst() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 10, 1, 2, 3, 4]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 14, 5]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_max_burst(4).unwrap();
+        block_on(sut.write_pwm(0, 10, &[1, 2, 3, 4, 5])).unwrap();
+        assert_eq!(block_on(sut.write_pwm(0, usize::MAX, &[0; 2])), Err(Error::IndexOutOfRange));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_display_frame_and_auto_play() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_PICTURE_FRAME, 5]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUTOPLAY_CONTROL1, 0x24, 10]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, 0x08 | 2]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.display_frame(Frame::new(5).unwrap())).unwrap();
+        let config = AutoPlay {
+            start_frame: Frame::new(2).unwrap(),
+            frame_count: 4,
+            loops: Loops::Count(2),
+            frame_delay: Duration::from_millis(108),
+        };
+        assert_eq!(block_on(sut.auto_play(&config)), Ok(Duration::from_millis(110)));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_frame_state() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x12]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let state = block_on(sut.frame_state()).unwrap();
+        assert_eq!(state, FrameState { frame: Frame::new(2).unwrap(), movie_finished: true });
+        i2c.done();
+    }
+
+    #[test]
+    fn test_clear_interrupt() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x02]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.clear_interrupt()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_wait_for_movie_end() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x17]),
+        ]);
+        let mut intb = PinMock::new(&[
+            PinTransaction::wait_for_state(State::Low),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let state = block_on(sut.wait_for_movie_end(&mut intb)).unwrap();
+        assert_eq!(state, FrameState { frame: Frame::new(7).unwrap(), movie_finished: true });
+        i2c.done();
+        intb.done();
+    }
+
+    #[test]
+    fn test_audio_play() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUTOPLAY_CONTROL1, 6]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, 0x10 | 1]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let config = AudioPlay {
+            start_frame: Frame::new(1).unwrap(),
+            frame_count: 6,
+            audio_sync: true,
+        };
+        block_on(sut.audio_play(&config)).unwrap();
+        assert_eq!(block_on(sut.audio_play(&AudioPlay { frame_count: 0, ..config })), Err(Error::InvalidConfig));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_breath() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_BREATH_CONTROL1, 0x55, 0x12]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let config = Breath {
+            fade_in: Duration::from_millis(800),
+            fade_out: Duration::from_millis(850),
+            extinguish: Duration::from_millis(14),
+            enable: true,
+        };
+        let steps = block_on(sut.breath(&config)).unwrap();
+        assert_eq!(steps, BreathSteps { fade_in: 5, fade_out: 5, extinguish: 2 });
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_led_and_blink_mask() {
+        let mut mask = [0u8; 18];
+        mask[0] = 0b1010_0101;
+        mask[17] = 0x80;
+        let mut expected_leds = vec![0x00];
+        expected_leds.extend_from_slice(&mask);
+        let mut expected_blink = vec![0x12];
+        expected_blink.extend_from_slice(&mask);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::GND as u8,
+                               expected_leds),
+            Transaction::write(Address::GND as u8,
+                               expected_blink),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.write_led_mask(&mask, 0)).unwrap();
+        block_on(sut.write_blink_mask(&mask, 0)).unwrap();
+        assert_eq!(block_on(sut.write_led_mask(&mask, 8)), Err(Error::InvalidFrame));
+        assert_eq!(block_on(sut.write_blink_mask(&mask, 8)), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_led_and_row_enabled() {
+        let mut expected = vec![0x00];
+        expected.extend_from_slice(&[0xFF; 18]);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x01], vec![0b0000_0001]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x01, 0b0000_1001]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x10, 0x00, 0x00]),
+            Transaction::write(Address::GND as u8,
+                               expected),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.set_led_enabled(11, true, 2)).unwrap();
+        block_on(sut.set_row_enabled(8, false, 2)).unwrap();
+        block_on(sut.set_all_leds_enabled(true, 2)).unwrap();
+        assert_eq!(block_on(sut.set_led_enabled(144, true, 2)), Err(Error::IndexOutOfRange));
+        assert_eq!(block_on(sut.set_row_enabled(9, true, 2)), Err(Error::InvalidCoordinate));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_led_and_row_blink() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 3]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x12 + 2], vec![0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x12 + 2, 0b0010_0000]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x12 + 6, 0xFF, 0xFF]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.set_led_blink(21, true, 3)).unwrap();
+        block_on(sut.set_row_blink(3, true, 3)).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_blink_and_period() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x21]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x29]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x29]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x2E]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x2E]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x2A]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        block_on(sut.blink(true)).unwrap();
+        block_on(sut.set_blink_period(6)).unwrap();
+        assert_eq!(block_on(sut.set_blink_period_duration(Duration::from_millis(600))), Ok(Duration::from_millis(540)));
+        assert_eq!(block_on(sut.set_blink_period(8)), Err(Error::InvalidConfig));
+        assert_eq!(block_on(sut.set_blink_period_duration(Duration::MAX)), Err(Error::InvalidConfig));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_reads() {
+        let pwm: Vec<u8> = (0..144).map(|i| i as u8).collect();
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_SHUTDOWN], vec![0x01]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 4]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x24], pwm.clone()),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x00], vec![0xFF; 18]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x12], vec![0x0F; 18]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(block_on(sut.read_function_register(ISSI_REG_SHUTDOWN)), Ok(0x01));
+        assert_eq!(block_on(sut.read_function_register(0x0D)), Err(Error::InvalidConfig));
+        assert_eq!(block_on(sut.read_pwm(4)).unwrap().as_slice(), pwm.as_slice());
+        assert_eq!(block_on(sut.read_led_mask(4)), Ok([0xFF; 18]));
+        assert_eq!(block_on(sut.read_blink_mask(4)), Ok([0x0F; 18]));
+        assert_eq!(block_on(sut.read_pwm(8)), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_bank_selection_invalidated_on_error() {
+        use embedded_hal::i2c::ErrorKind;
+
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 1])
+                .with_error(ErrorKind::Bus),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x25, 2]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(block_on(sut.set_pwm_by_index(0, 1, 2)), Err(Error::I2c(ErrorKind::Bus)));
+        block_on(sut.set_pwm_by_index(0, 1, 2)).unwrap();
+        block_on(sut.set_pwm_by_index(1, 2, 2)).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_hard_reset() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x01]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE]),
+        ]);
+        let mut sdb = PinMock::new(&[
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High),
+        ]);
+
+        let mut sut = IS31FL3731::with_sdb(i2c.clone(), DelayStub{}, Address::GND, sdb.clone());
+
+        block_on(sut.hard_reset()).unwrap();
+        i2c.done();
+        sdb.done();
+    }
+
+    #[test]
+    fn test_set_pixel_and_draw_text() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 6]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 2 * 16 + 12, 40]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 3 * 16 + 13, 40]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 4 * 16 + 14, 40]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 117, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 68, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 101, 3]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(block_on(sut.draw_text(6, 11, 2, "`", 40)), Ok(5));
+        assert_eq!(block_on(sut.set_pixel(16, 0, 10, 6)), Err(Error::InvalidCoordinate));
+
+        let mut sut = sut.with_layout(boards::PimoroniLedShim);
+        block_on(sut.set_pixel_rgb(1, 0, [1, 2, 3], 1)).unwrap();
+        assert_eq!(block_on(sut.set_pixel_rgb(28, 0, [1, 2, 3], 1)), Err(Error::InvalidCoordinate));
+        i2c.done();
+    }
+}
@@ -0,0 +1,210 @@
+//! Pixel mappings for commercial IS31FL3731 boards, ported from the vendors' reference libraries.
+
+use crate::{Layout, Linear, RgbLayout};
+
+/// Adafruit 16x9 CharliePlex LED matrix breakout. Its LEDs are wired in the chip's native
+/// order, matching `x + y * 16` in Adafruit's libraries, so it is the same as [`Linear`].
+pub type AdafruitMatrix16x9 = Linear;
+
+/// Adafruit 15x7 CharliePlex FeatherWing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdafruitCharlieWing;
+
+impl Layout for AdafruitCharlieWing {
+    fn width(&self) -> usize {
+        15
+    }
+
+    fn height(&self) -> usize {
+        7
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width() || y >= self.height() {
+            return None;
+        }
+
+        // the right half is mounted upside down on the second matrix
+        let (x, y) = if x > 7 { (15 - x, y + 8) } else { (x, 7 - y) };
+        Some(x * 16 + y)
+    }
+}
+
+/// Pimoroni Scroll pHAT HD (17x7).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PimoroniScrollPhatHd;
+
+impl Layout for PimoroniScrollPhatHd {
+    fn width(&self) -> usize {
+        17
+    }
+
+    fn height(&self) -> usize {
+        7
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width() || y >= self.height() {
+            return None;
+        }
+
+        if x <= 8 {
+            Some((8 - x) * 16 + (6 - y))
+        } else {
+            Some((x - 9) * 16 + 8 + y)
+        }
+    }
+}
+
+const LED_SHIM_LOOKUP:[[usize; 3]; 28] = [
+    [118, 69, 85], [117, 68, 101], [116, 84, 100], [115, 83, 99], [114, 82, 98], [113, 81, 97], [112, 80, 96],
+    [134, 21, 37], [133, 20, 36], [132, 19, 35], [131, 18, 34], [130, 17, 50], [129, 33, 49], [128, 32, 48],
+    [127, 47, 63], [121, 41, 57], [122, 25, 58], [123, 26, 42], [124, 27, 43], [125, 28, 44], [126, 29, 45],
+    [15, 95, 111], [8, 89, 105], [9, 90, 106], [10, 91, 107], [11, 92, 108], [12, 76, 109], [13, 77, 93],
+];
+
+/// Pimoroni LED SHIM: a single row of 28 RGB pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PimoroniLedShim;
+
+impl RgbLayout for PimoroniLedShim {
+    fn width(&self) -> usize {
+        LED_SHIM_LOOKUP.len()
+    }
+
+    fn height(&self) -> usize {
+        1
+    }
+
+    fn rgb_index(&self, x: usize, y: usize) -> Option<[usize; 3]> {
+        if y != 0 {
+            return None;
+        }
+
+        LED_SHIM_LOOKUP.get(x).copied()
+    }
+}
+
+const RGB_MATRIX_5X5_LOOKUP:[[usize; 3]; 25] = [
+    [118, 69, 85], [117, 68, 101], [116, 84, 100], [115, 83, 99], [114, 82, 98],
+    [132, 19, 35], [133, 20, 36], [134, 21, 37], [112, 80, 96], [113, 81, 97],
+    [131, 18, 34], [130, 17, 50], [129, 33, 49], [128, 32, 48], [127, 47, 63],
+    [125, 28, 44], [124, 27, 43], [123, 26, 42], [122, 25, 58], [121, 41, 57],
+    [126, 29, 45], [15, 95, 111], [8, 89, 105], [9, 90, 106], [10, 91, 107],
+];
+
+/// Pimoroni 5x5 RGB Matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PimoroniRgbMatrix5x5;
+
+impl RgbLayout for PimoroniRgbMatrix5x5 {
+    fn width(&self) -> usize {
+        5
+    }
+
+    fn height(&self) -> usize {
+        5
+    }
+
+    fn rgb_index(&self, x: usize, y: usize) -> Option<[usize; 3]> {
+        if x >= self.width() || y >= self.height() {
+            return None;
+        }
+
+        Some(RGB_MATRIX_5X5_LOOKUP[x + y * 5])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_unique(layout: &impl Layout) {
+        let mut seen = [false; 144];
+        for y in 0..layout.height() {
+            for x in 0..layout.width() {
+                let index = layout.index(x, y).unwrap();
+                assert!(!seen[index], "({}, {}) reuses register {}", x, y, index);
+                seen[index] = true;
+            }
+        }
+    }
+
+    fn assert_rgb_unique(layout: &impl RgbLayout) {
+        let mut seen = [false; 144];
+        for y in 0..layout.height() {
+            for x in 0..layout.width() {
+                for index in layout.rgb_index(x, y).unwrap() {
+                    assert!(!seen[index], "({}, {}) reuses register {}", x, y, index);
+                    seen[index] = true;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adafruit_matrix_16x9() {
+        let sut = AdafruitMatrix16x9::default();
+
+        assert_eq!(sut.index(0, 0), Some(0));
+        assert_eq!(sut.index(15, 0), Some(15));
+        assert_eq!(sut.index(0, 1), Some(16));
+        assert_eq!(sut.index(15, 8), Some(143));
+        assert_eq!(sut.index(16, 0), None);
+        assert_unique(&sut);
+    }
+
+    #[test]
+    fn test_adafruit_charlie_wing() {
+        let sut = AdafruitCharlieWing;
+
+        assert_eq!(sut.index(0, 0), Some(7));
+        assert_eq!(sut.index(0, 6), Some(1));
+        assert_eq!(sut.index(7, 0), Some(119));
+        assert_eq!(sut.index(7, 6), Some(113));
+        assert_eq!(sut.index(8, 0), Some(120));
+        assert_eq!(sut.index(14, 0), Some(24));
+        assert_eq!(sut.index(14, 6), Some(30));
+        assert_eq!(sut.index(15, 0), None);
+        assert_eq!(sut.index(0, 7), None);
+        assert_unique(&sut);
+    }
+
+    #[test]
+    fn test_pimoroni_scroll_phat_hd() {
+        let sut = PimoroniScrollPhatHd;
+
+        assert_eq!(sut.index(0, 0), Some(134));
+        assert_eq!(sut.index(0, 6), Some(128));
+        assert_eq!(sut.index(8, 0), Some(6));
+        assert_eq!(sut.index(9, 0), Some(8));
+        assert_eq!(sut.index(16, 6), Some(126));
+        assert_eq!(sut.index(17, 0), None);
+        assert_eq!(sut.index(0, 7), None);
+        assert_unique(&sut);
+    }
+
+    #[test]
+    fn test_pimoroni_led_shim() {
+        let sut = PimoroniLedShim;
+
+        assert_eq!((sut.width(), sut.height()), (28, 1));
+        assert_eq!(sut.rgb_index(0, 0), Some([118, 69, 85]));
+        assert_eq!(sut.rgb_index(27, 0), Some([13, 77, 93]));
+        assert_eq!(sut.rgb_index(28, 0), None);
+        assert_eq!(sut.rgb_index(0, 1), None);
+        assert_rgb_unique(&sut);
+    }
+
+    #[test]
+    fn test_pimoroni_rgb_matrix_5x5() {
+        let sut = PimoroniRgbMatrix5x5;
+
+        assert_eq!(sut.rgb_index(0, 0), Some([118, 69, 85]));
+        assert_eq!(sut.rgb_index(4, 0), Some([114, 82, 98]));
+        assert_eq!(sut.rgb_index(0, 1), Some([132, 19, 35]));
+        assert_eq!(sut.rgb_index(4, 4), Some([10, 91, 107]));
+        assert_eq!(sut.rgb_index(5, 0), None);
+        assert_rgb_unique(&sut);
+    }
+}
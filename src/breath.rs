@@ -0,0 +1,106 @@
+use core::time::Duration;
+
+const FADE_UNIT_US:u64 = 26_000;
+const EXTINGUISH_UNIT_US:u64 = 3_500;
+const STEP_MAX:u8 = 7;
+const BREATH_ENABLE:u8 = 0x10;
+
+/// Hardware breathing configuration for the Breath Control registers.
+///
+/// Fade times are rounded to the nearest of 26 ms × 2^n and the extinguish time to the
+/// nearest of 3.5 ms × 2^n, with n in 0..=7.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Breath {
+    pub fade_in: Duration,
+    pub fade_out: Duration,
+    pub extinguish: Duration,
+    pub enable: bool,
+}
+
+/// The exponent n picked for each of the [`Breath`] timings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BreathSteps {
+    pub fade_in: u8,
+    pub fade_out: u8,
+    pub extinguish: u8,
+}
+
+impl BreathSteps {
+    pub fn fade_in(&self) -> Duration {
+        step_duration(FADE_UNIT_US, self.fade_in)
+    }
+
+    pub fn fade_out(&self) -> Duration {
+        step_duration(FADE_UNIT_US, self.fade_out)
+    }
+
+    pub fn extinguish(&self) -> Duration {
+        step_duration(EXTINGUISH_UNIT_US, self.extinguish)
+    }
+}
+
+impl Breath {
+    pub fn steps(&self) -> BreathSteps {
+        BreathSteps {
+            fade_in: nearest_step(FADE_UNIT_US, self.fade_in),
+            fade_out: nearest_step(FADE_UNIT_US, self.fade_out),
+            extinguish: nearest_step(EXTINGUISH_UNIT_US, self.extinguish),
+        }
+    }
+
+    /// Returns the Breath Control 1/2 register values and the steps they encode.
+    pub(crate) fn encode(&self) -> (u8, u8, BreathSteps) {
+        let steps = self.steps();
+        let control1 = (steps.fade_out << 4) | steps.fade_in;
+        let control2 = if self.enable { BREATH_ENABLE } else { 0 } | steps.extinguish;
+
+        (control1, control2, steps)
+    }
+}
+
+fn step_duration(unit_us: u64, step: u8) -> Duration {
+    Duration::from_micros(unit_us << step)
+}
+
+fn nearest_step(unit_us: u64, duration: Duration) -> u8 {
+    let target = duration.as_micros();
+    (0..=STEP_MAX)
+        .min_by_key(|&step| target.abs_diff(step_duration(unit_us, step).as_micros()))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps() {
+        let sut = Breath {
+            fade_in: Duration::from_millis(0),
+            fade_out: Duration::from_millis(400),
+            extinguish: Duration::from_secs(10),
+            enable: true,
+        };
+
+        let steps = sut.steps();
+        assert_eq!(steps, BreathSteps { fade_in: 0, fade_out: 4, extinguish: 7 });
+        assert_eq!(steps.fade_in(), Duration::from_millis(26));
+        assert_eq!(steps.fade_out(), Duration::from_millis(416));
+        assert_eq!(steps.extinguish(), Duration::from_millis(448));
+    }
+
+    #[test]
+    fn test_encode() {
+        let sut = Breath {
+            fade_in: Duration::from_millis(210),
+            fade_out: Duration::from_millis(3000),
+            extinguish: Duration::from_micros(3_500),
+            enable: true,
+        };
+        assert_eq!(sut.encode().0, 0x73);
+        assert_eq!(sut.encode().1, 0x10);
+
+        let sut = Breath { enable: false, extinguish: Duration::from_millis(30), ..sut };
+        assert_eq!(sut.encode().1, 0x03);
+    }
+}
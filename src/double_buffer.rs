@@ -0,0 +1,150 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::I2c;
+
+use crate::{Error, Frame, IS31FL3731, ISSI_PWM_LEN, Linear, NoPin};
+
+/// Draws into a hidden frame bank and shows it with a single Picture Display register write.
+pub struct DoubleBuffer<I2C, Delay, Sdb = NoPin, L = Linear>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin
+{
+    driver: IS31FL3731<I2C, Delay, Sdb, L>,
+    front: Frame,
+    back: Frame,
+}
+
+impl<I2C, Delay, Sdb, L, I2cError> DoubleBuffer<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin {
+    /// Shows `front` and uses `back` as the drawing frame. Both must be distinct.
+    pub fn new(mut driver: IS31FL3731<I2C, Delay, Sdb, L>, front: Frame, back: Frame) -> Result<Self, Error<I2cError>> {
+        if front == back {
+            return Err(Error::InvalidFrame);
+        }
+
+        driver.display_frame(front)?;
+
+        Ok(Self {
+            driver,
+            front,
+            back,
+        })
+    }
+
+    pub fn front(&self) -> Frame {
+        self.front
+    }
+
+    pub fn back(&self) -> Frame {
+        self.back
+    }
+
+    pub fn clear(&mut self) -> Result<(), Error<I2cError>> {
+        self.driver.clear(self.back)
+    }
+
+    pub fn set_pwm_by_index(&mut self, index: usize, pwm: u8) -> Result<(), Error<I2cError>> {
+        self.driver.set_pwm_by_index(index, pwm, self.back)
+    }
+
+    pub fn write_frame(&mut self, pwm: &[u8; ISSI_PWM_LEN]) -> Result<(), Error<I2cError>> {
+        self.driver.write_frame(self.back, pwm)
+    }
+
+    /// Displays the back frame and makes the previously shown frame the new drawing frame.
+    ///
+    /// The new back frame still holds what was drawn two flips ago, so redraw all of it with
+    /// [`Self::write_frame`] or [`Self::clear`] before the next flip, or use
+    /// [`Self::flip_and_clear`]. Otherwise stale pixels come back on screen.
+    pub fn flip(&mut self) -> Result<(), Error<I2cError>> {
+        self.driver.display_frame(self.back)?;
+        core::mem::swap(&mut self.front, &mut self.back);
+
+        Ok(())
+    }
+
+    /// Like [`Self::flip`], then blanks the new back frame so partial updates start from black.
+    pub fn flip_and_clear(&mut self) -> Result<(), Error<I2cError>> {
+        self.flip()?;
+        self.clear()
+    }
+
+    pub fn driver(&mut self) -> &mut IS31FL3731<I2C, Delay, Sdb, L> {
+        &mut self.driver
+    }
+
+    pub fn release(self) -> IS31FL3731<I2C, Delay, Sdb, L> {
+        self.driver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+    use crate::test_util::{vec, DelayStub};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn test_flip() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8, vec![0xFD, 0x0B]),
+            Transaction::write(Address::GND as u8, vec![0x01, 0]),
+            Transaction::write(Address::GND as u8, vec![0xFD, 1]),
+            Transaction::write(Address::GND as u8, vec![0x24 + 3, 200]),
+            Transaction::write(Address::GND as u8, vec![0xFD, 0x0B]),
+            Transaction::write(Address::GND as u8, vec![0x01, 1]),
+            Transaction::write(Address::GND as u8, vec![0xFD, 0]),
+            Transaction::write(Address::GND as u8, vec![0x24 + 4, 100]),
+        ]);
+
+        let driver = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+        let mut sut = DoubleBuffer::new(driver, Frame::new(0).unwrap(), Frame::new(1).unwrap()).unwrap();
+
+        sut.set_pwm_by_index(3, 200).unwrap();
+        sut.flip().unwrap();
+        assert_eq!(sut.front(), Frame::new(1).unwrap());
+        assert_eq!(sut.back(), Frame::new(0).unwrap());
+        sut.set_pwm_by_index(4, 100).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_flip_and_clear() {
+        let mut transactions = vec![
+            Transaction::write(Address::GND as u8, vec![0xFD, 0x0B]),
+            Transaction::write(Address::GND as u8, vec![0x01, 2]),
+            Transaction::write(Address::GND as u8, vec![0x01, 5]),
+            Transaction::write(Address::GND as u8, vec![0xFD, 2]),
+        ];
+        for x in 0..6 {
+            let mut erase = vec![0; 25];
+            erase[0] = 0x24 + x * 24;
+            transactions.push(Transaction::write(Address::GND as u8, erase));
+        }
+        let mut i2c = Mock::new(&transactions);
+
+        let driver = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+        let mut sut = DoubleBuffer::new(driver, Frame::new(2).unwrap(), Frame::new(5).unwrap()).unwrap();
+
+        sut.flip_and_clear().unwrap();
+        assert_eq!(sut.front(), Frame::new(5).unwrap());
+        assert_eq!(sut.back(), Frame::new(2).unwrap());
+        i2c.done();
+    }
+
+    #[test]
+    fn test_new_same_frames() {
+        let mut i2c = Mock::new(&[]);
+
+        let driver = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+        let frame = Frame::new(2).unwrap();
+        assert!(matches!(DoubleBuffer::new(driver, frame, frame), Err(Error::InvalidFrame)));
+        i2c.done();
+    }
+}
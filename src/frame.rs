@@ -0,0 +1,64 @@
+use crate::FRAME_COUNT;
+
+/// One of the chip's eight frame banks, guaranteed to be in 0..8.
+///
+/// Every method that takes a frame bank accepts a `Frame` or a plain `u8`; the latter is
+/// range-checked on each call and rejected with [`crate::Error::InvalidFrame`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Frame(u8);
+
+impl Frame {
+    pub const fn new(frame: u8) -> Option<Self> {
+        if frame < FRAME_COUNT {
+            Some(Frame(frame))
+        } else {
+            None
+        }
+    }
+
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<Frame> for u8 {
+    fn from(frame: Frame) -> Self {
+        frame.0
+    }
+}
+
+/// Contents of the Frame State register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameState {
+    /// Frame currently being displayed.
+    pub frame: Frame,
+    /// Set by the chip when an Auto Frame Play movie has finished; INTB is asserted alongside it.
+    pub movie_finished: bool,
+}
+
+impl FrameState {
+    pub(crate) fn decode(data: u8) -> Self {
+        FrameState {
+            frame: Frame(data & 0x07),
+            movie_finished: data & 0x10 != 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert_eq!(Frame::new(0).map(Frame::index), Some(0));
+        assert_eq!(Frame::new(7).map(u8::from), Some(7));
+        assert_eq!(Frame::new(8), None);
+    }
+
+    #[test]
+    fn test_decode_frame_state() {
+        assert_eq!(FrameState::decode(0x13), FrameState { frame: Frame(3), movie_finished: true });
+        assert_eq!(FrameState::decode(0x07), FrameState { frame: Frame(7), movie_finished: false });
+    }
+}
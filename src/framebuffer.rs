@@ -0,0 +1,264 @@
+use core::iter;
+use core::ops::Range;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::I2c;
+
+use crate::font;
+use crate::{Error, Frame, IS31FL3731, ISSI_PWM_LEN, MATRIX_HEIGHT, MATRIX_WIDTH};
+
+// a new transfer costs the address and register bytes, so bridging a gap of up to two
+// unchanged pixels is cheaper than starting another burst
+const MAX_GAP:usize = 2;
+
+/// 16×9 PWM values kept in MCU memory, tracking which pixels changed since the last flush.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameBuffer {
+    pwm: [u8; ISSI_PWM_LEN],
+    dirty: [u16; MATRIX_HEIGHT],
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameBuffer {
+    /// Creates a blank buffer. All pixels start dirty so the first flush uploads everything.
+    pub const fn new() -> Self {
+        Self {
+            pwm: [0; ISSI_PWM_LEN],
+            dirty: [u16::MAX; MATRIX_HEIGHT],
+        }
+    }
+
+    /// Sets a pixel. Pixels outside the 16×9 matrix are ignored.
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+        if x >= MATRIX_WIDTH || y >= MATRIX_HEIGHT {
+            return;
+        }
+
+        let index = y * MATRIX_WIDTH + x;
+        if self.pwm[index] != value {
+            self.pwm[index] = value;
+            self.dirty[y] |= 1 << x;
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u8> {
+        if x >= MATRIX_WIDTH || y >= MATRIX_HEIGHT {
+            return None;
+        }
+
+        Some(self.pwm[y * MATRIX_WIDTH + x])
+    }
+
+    pub fn fill(&mut self, value: u8) {
+        for y in 0..MATRIX_HEIGHT {
+            for x in 0..MATRIX_WIDTH {
+                self.set_pixel(x, y, value);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(0);
+    }
+
+    /// Draws `text` with the built-in 5×7 font, its top-left corner at `(x, y)`. Pixels falling
+    /// outside the matrix are clipped. Returns the width of the whole text in pixels.
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, brightness: u8) -> usize {
+        for (px, py) in font::pixels(text, x, y, MATRIX_WIDTH, MATRIX_HEIGHT) {
+            self.set_pixel(px, py, brightness);
+        }
+
+        font::text_width(text)
+    }
+
+    pub fn pwm(&self) -> &[u8; ISSI_PWM_LEN] {
+        &self.pwm
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(|&row| row != 0)
+    }
+
+    /// Forces the next flush to upload every pixel, e.g. after the chip was reset.
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = [u16::MAX; MATRIX_HEIGHT];
+    }
+
+    /// Writes the pixels changed since the last flush to `frame`, one burst per run of changes.
+    pub fn flush<I2C, Delay, Sdb, L, I2cError>(&mut self, driver: &mut IS31FL3731<I2C, Delay, Sdb, L>, frame: impl Into<u8>) -> Result<(), Error<I2cError>>
+    where
+        I2C: I2c<Error = I2cError>,
+        Delay: DelayNs,
+        Sdb: OutputPin {
+        let frame = Frame::new(frame.into()).ok_or(Error::InvalidFrame)?;
+        for run in self.dirty_runs() {
+            driver.write_pwm(frame, run.start, &self.pwm[run])?;
+        }
+
+        self.dirty = [0; MATRIX_HEIGHT];
+
+        Ok(())
+    }
+
+    /// Like [`Self::flush`], through the async driver.
+    #[cfg(feature = "async")]
+    pub async fn flush_async<I2C, Delay, Sdb, L, I2cError>(&mut self, driver: &mut crate::asynch::IS31FL3731<I2C, Delay, Sdb, L>, frame: impl Into<u8>) -> Result<(), Error<I2cError>>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = I2cError>,
+        Delay: embedded_hal_async::delay::DelayNs,
+        Sdb: OutputPin {
+        let frame = Frame::new(frame.into()).ok_or(Error::InvalidFrame)?;
+        for run in self.dirty_runs() {
+            driver.write_pwm(frame, run.start, &self.pwm[run]).await?;
+        }
+
+        self.dirty = [0; MATRIX_HEIGHT];
+
+        Ok(())
+    }
+
+    /// Yields the runs of dirty pixels to upload, bridging gaps of up to [`MAX_GAP`] pixels.
+    fn dirty_runs(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let mut index = 0;
+        iter::from_fn(move || {
+            let start = (index..ISSI_PWM_LEN).find(|&i| self.is_pixel_dirty(i))?;
+            let mut end = start + 1;
+            while let Some(next) = (end..ISSI_PWM_LEN.min(end + MAX_GAP + 1)).find(|&i| self.is_pixel_dirty(i)) {
+                end = next + 1;
+            }
+
+            index = end;
+            Some(start..end)
+        })
+    }
+
+    fn is_pixel_dirty(&self, index: usize) -> bool {
+        self.dirty[index / MATRIX_WIDTH] & (1 << (index % MATRIX_WIDTH)) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+    use crate::test_util::{vec, DelayStub};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn test_set_and_get_pixel() {
+        let mut sut = FrameBuffer::new();
+
+        sut.set_pixel(15, 8, 42);
+        sut.set_pixel(16, 0, 1);
+        sut.set_pixel(0, 9, 1);
+        assert_eq!(sut.get_pixel(15, 8), Some(42));
+        assert_eq!(sut.get_pixel(16, 0), None);
+        assert_eq!(sut.pwm()[143], 42);
+        assert_eq!(sut.pwm().iter().filter(|&&v| v != 0).count(), 1);
+    }
+
+    #[test]
+    fn test_first_flush_uploads_everything() {
+        let mut expected = vec![0x24];
+        expected.extend_from_slice(&[7; 144]);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8, vec![0xFD, 1]),
+            Transaction::write(Address::GND as u8, expected),
+        ]);
+        let mut driver = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let mut sut = FrameBuffer::new();
+        sut.fill(7);
+        sut.flush(&mut driver, 1).unwrap();
+        assert!(!sut.is_dirty());
+        i2c.done();
+    }
+
+    #[test]
+    fn test_flush_sends_changed_runs() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8, vec![0xFD, 0]),
+            Transaction::write(Address::GND as u8, vec![0x24 + 2, 1, 0, 0, 2]),
+            Transaction::write(Address::GND as u8, vec![0x24 + 16 * 8 + 15, 3]),
+        ]);
+        let mut driver = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let mut sut = FrameBuffer::new();
+        sut.dirty = [0; MATRIX_HEIGHT];
+        sut.set_pixel(2, 0, 1);
+        sut.set_pixel(5, 0, 2);
+        sut.set_pixel(9, 0, 0);
+        sut.set_pixel(15, 8, 3);
+        sut.flush(&mut driver, 0).unwrap();
+        sut.flush(&mut driver, 0).unwrap();
+        assert_eq!(sut.flush(&mut driver, 200), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_flush_async() {
+        use crate::test_util::block_on;
+
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8, vec![0xFD, 3]),
+            Transaction::write(Address::GND as u8, vec![0x24 + 16 + 4, 8]),
+        ]);
+        let mut driver = crate::asynch::IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let mut sut = FrameBuffer::new();
+        sut.dirty = [0; MATRIX_HEIGHT];
+        sut.set_pixel(4, 1, 8);
+        block_on(sut.flush_async(&mut driver, 3)).unwrap();
+        block_on(sut.flush_async(&mut driver, 3)).unwrap();
+        assert_eq!(block_on(sut.flush_async(&mut driver, 8)), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let mut sut = FrameBuffer::new();
+
+        assert_eq!(sut.draw_text(0, 1, "I", 50), 5);
+        assert_eq!(sut.get_pixel(2, 1), Some(50));
+        assert_eq!(sut.get_pixel(2, 7), Some(50));
+        assert_eq!(sut.get_pixel(1, 1), Some(50));
+        assert_eq!(sut.get_pixel(0, 1), Some(0));
+        assert_eq!(sut.pwm().iter().filter(|&&v| v != 0).count(), 11);
+    }
+
+    #[test]
+    fn test_draw_text_clipped() {
+        let mut sut = FrameBuffer::new();
+
+        // the second 'l' only has its bottom stroke left on screen, at x = 0
+        assert_eq!(sut.draw_text(-9, 0, "llo", 9), 17);
+        assert_eq!(sut.get_pixel(0, 6), Some(9));
+        assert_eq!(sut.get_pixel(0, 5), Some(0));
+        assert_eq!(sut.get_pixel(2, 4), Some(0));
+        assert_eq!(sut.get_pixel(3, 4), Some(9));
+
+        let mut sut = FrameBuffer::new();
+        sut.draw_text(14, 2, "A", 1);
+        sut.draw_text(0, -8, "A", 1);
+        assert_eq!(sut.get_pixel(14, 3), Some(1));
+        assert_eq!(sut.get_pixel(15, 2), Some(1));
+        assert_eq!(sut.pwm().iter().filter(|&&v| v != 0).count(), 8);
+    }
+
+    #[test]
+    fn test_draw_text_far_away() {
+        let mut sut = FrameBuffer::new();
+
+        assert_eq!(sut.draw_text(i32::MAX - 3, 0, "AB", 1), 11);
+        assert_eq!(sut.draw_text(0, i32::MAX - 3, "AB", 1), 11);
+        assert_eq!(sut.draw_text(i32::MIN, i32::MIN, "AB", 1), 11);
+        assert_eq!(sut.pwm(), &[0; ISSI_PWM_LEN]);
+    }
+}
@@ -0,0 +1,64 @@
+use core::convert::Infallible;
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::{Gray8, GrayColor};
+use embedded_graphics_core::Pixel;
+
+use crate::{FrameBuffer, MATRIX_HEIGHT, MATRIX_WIDTH};
+
+/// Draw into the buffer with embedded-graphics, then [`FrameBuffer::flush`] it to a frame bank.
+/// The luma of each pixel is used as its PWM value.
+impl DrawTarget for FrameBuffer {
+    type Color = Gray8;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>> {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y)) {
+                self.set_pixel(x, y, color.luma());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        Size::new(MATRIX_WIDTH as u32, MATRIX_HEIGHT as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics_core::geometry::Point;
+    use embedded_graphics_core::primitives::Rectangle;
+    use embedded_graphics_core::Drawable;
+
+    #[test]
+    fn test_draw_pixels() {
+        let mut sut = FrameBuffer::new();
+
+        Pixel(Point::new(3, 4), Gray8::new(99)).draw(&mut sut).unwrap();
+        Pixel(Point::new(-1, 4), Gray8::WHITE).draw(&mut sut).unwrap();
+        Pixel(Point::new(16, 0), Gray8::WHITE).draw(&mut sut).unwrap();
+
+        assert_eq!(sut.get_pixel(3, 4), Some(99));
+        assert_eq!(sut.pwm().iter().filter(|&&v| v != 0).count(), 1);
+    }
+
+    #[test]
+    fn test_fill_solid_is_clipped() {
+        let mut sut = FrameBuffer::new();
+
+        sut.fill_solid(&Rectangle::new(Point::new(14, 7), Size::new(4, 4)), Gray8::WHITE).unwrap();
+
+        assert_eq!(sut.size(), Size::new(16, 9));
+        assert_eq!(sut.pwm().iter().filter(|&&v| v == 255).count(), 4);
+        assert_eq!(sut.get_pixel(15, 8), Some(255));
+    }
+}
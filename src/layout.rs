@@ -0,0 +1,59 @@
+use crate::{MATRIX_HEIGHT, MATRIX_WIDTH};
+
+/// Maps logical `(x, y)` pixel coordinates of a board onto the chip's 144 LED register indices.
+pub trait Layout {
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    /// Returns the register index (0..144) of the pixel, or `None` if it is outside the layout.
+    fn index(&self, x: usize, y: usize) -> Option<usize>;
+}
+
+/// Maps logical pixels of an RGB board onto the register indices of their red, green and blue LEDs.
+pub trait RgbLayout {
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    /// Returns the `[red, green, blue]` register indices of the pixel, or `None` if it is outside
+    /// the layout.
+    fn rgb_index(&self, x: usize, y: usize) -> Option<[usize; 3]>;
+}
+
+/// The chip's native order: 16 columns by 9 rows, row by row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Linear;
+
+impl Layout for Linear {
+    fn width(&self) -> usize {
+        MATRIX_WIDTH
+    }
+
+    fn height(&self) -> usize {
+        MATRIX_HEIGHT
+    }
+
+    fn index(&self, x: usize, y: usize) -> Option<usize> {
+        if x < MATRIX_WIDTH && y < MATRIX_HEIGHT {
+            Some(y * MATRIX_WIDTH + x)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear() {
+        assert_eq!((Linear.width(), Linear.height()), (16, 9));
+        assert_eq!(Linear.index(0, 0), Some(0));
+        assert_eq!(Linear.index(3, 2), Some(35));
+        assert_eq!(Linear.index(15, 8), Some(143));
+        assert_eq!(Linear.index(16, 0), None);
+        assert_eq!(Linear.index(0, 9), None);
+    }
+}
@@ -1,14 +1,60 @@
+#![no_std]
+
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod boards;
+mod breath;
+mod double_buffer;
+pub mod font;
+mod frame;
+mod framebuffer;
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
+mod layout;
+mod marquee;
+mod mode;
+mod tiled;
+
+pub use breath::{Breath, BreathSteps};
+pub use double_buffer::DoubleBuffer;
+pub use frame::{Frame, FrameState};
+pub use framebuffer::FrameBuffer;
+pub use layout::{Layout, Linear, RgbLayout};
+pub use marquee::{Direction, Marquee, MarqueeConfig, MarqueeState};
+pub use tiled::{Rotation, Tile, TiledDisplay};
+pub use mode::{AudioPlay, AutoPlay, Loops};
+
+use core::time::Duration;
 use embedded_hal::delay::DelayNs;
-use embedded_hal::i2c::I2c;
+use embedded_hal::digital::{self, InputPin, OutputPin};
+use embedded_hal::i2c::{self, I2c};
 
 const MATRIX_WIDTH:usize = 16;
 const MATRIX_HEIGHT:usize = 9;
+const FRAME_COUNT:u8 = 8;
 const ISSI_COMMAND_REGISTER:u8 = 0xFD;
 const ISSI_BANK_FUNCTION_REGISTER:u8 = 0x0B;
 const ISSI_REG_SHUTDOWN:u8 = 0x0A;
 const ISSI_REG_CONFIG:u8 = 0x00;
 const ISSI_REG_CONFIG_PICTURE_MODE:u8 = 0x00;
+const ISSI_REG_CONFIG_AUTOPLAY_MODE:u8 = 0x08;
+const ISSI_REG_CONFIG_AUDIOPLAY_MODE:u8 = 0x10;
+const ISSI_REG_PICTURE_FRAME:u8 = 0x01;
+const ISSI_REG_FRAME_STATE:u8 = 0x07;
+const ISSI_REG_AUTOPLAY_CONTROL1:u8 = 0x02;
 const ISSI_REG_AUDIOSYNC:u8 = 0x06;
+const ISSI_REG_DISPLAY_OPTION:u8 = 0x05;
+const ISSI_REG_BREATH_CONTROL1:u8 = 0x08;
+const ISSI_REG_AUDIO_ADC_RATE:u8 = 0x0C;
+const ISSI_DISPLAY_OPTION_BLINK_ENABLE:u8 = 0x08;
+const ISSI_DISPLAY_OPTION_BLINK_PERIOD_MASK:u8 = 0x07;
+const ISSI_REG_LED_CONTROL:u8 = 0x00;
+const ISSI_REG_BLINK_CONTROL:u8 = 0x12;
+const ISSI_REG_PWM:u8 = 0x24;
+const ISSI_LED_CONTROL_LEN:usize = 18;
+const ISSI_PWM_LEN:usize = MATRIX_WIDTH*MATRIX_HEIGHT;
+const BLINK_PERIOD_UNIT_MS:u64 = 270;
+const SDB_WAKE_DELAY_MS:u32 = 1;
 
 #[derive(Clone, Copy)]
 pub enum Address {
@@ -18,219 +64,1534 @@ pub enum Address {
     SDA = 0b1110110,
 }
 
-pub struct IS31FL3731<I2C, Delay>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The underlying I2C bus reported an error.
+    I2c(E),
+    /// A frame outside 0..8 was requested.
+    InvalidFrame,
+    /// A LED/PWM index outside the 144 LED matrix was requested.
+    IndexOutOfRange,
+    /// An (x, y) coordinate outside the display was requested.
+    InvalidCoordinate,
+    /// A configuration value cannot be represented by the chip.
+    InvalidConfig,
+    /// The chip did not reach the expected state in time.
+    Timeout,
+    /// Accessing the SDB or INTB pin failed.
+    Pin,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(error: E) -> Self {
+        Error::I2c(error)
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "I2C error: {:?}", e),
+            Error::InvalidFrame => f.write_str("invalid frame"),
+            Error::IndexOutOfRange => f.write_str("LED index out of range"),
+            Error::InvalidCoordinate => f.write_str("invalid coordinate"),
+            Error::InvalidConfig => f.write_str("invalid configuration"),
+            Error::Timeout => f.write_str("timeout"),
+            Error::Pin => f.write_str("pin error"),
+        }
+    }
+}
+
+impl<E: i2c::Error> i2c::Error for Error<E> {
+    fn kind(&self) -> i2c::ErrorKind {
+        match self {
+            Error::I2c(e) => e.kind(),
+            _ => i2c::ErrorKind::Other,
+        }
+    }
+}
+
+/// Stand-in for boards that do not wire the SDB pin to a GPIO.
+pub struct NoPin;
+
+impl digital::ErrorType for NoPin {
+    type Error = core::convert::Infallible;
+}
+
+impl OutputPin for NoPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Blocking IS31FL3731 driver.
+///
+/// The driver takes ownership of its `I2C`, so boards that share one bus between several devices
+/// should hand it a shared-bus wrapper from `embedded-hal-bus` such as `RefCellDevice` or
+/// `CriticalSectionDevice`, one per device. The driver caches the bank it last selected, so use a
+/// single driver per chip and get the bus back with [`Self::release`] when done.
+pub struct IS31FL3731<I2C, Delay, Sdb = NoPin, L = Linear>
 where
     I2C: I2c,
-    Delay: DelayNs
+    Delay: DelayNs,
+    Sdb: OutputPin
 {
     i2c: I2C,
     delay: Delay,
-    frame: u8,
+    sdb: Sdb,
+    layout: L,
+    /// Bank last written to the command register, `None` when unknown.
+    bank: Option<u8>,
+    max_burst: usize,
     address: Address,
 }
 
-impl<I2C, Delay, I2cError> IS31FL3731<I2C, Delay>
+impl<I2C, Delay> IS31FL3731<I2C, Delay>
 where
-    I2C: I2c<Error = I2cError>,
+    I2C: I2c,
     Delay: DelayNs {
     pub fn new(i2c: I2C, delay: Delay, address: Address) -> Self {
+        Self::with_sdb(i2c, delay, address, NoPin)
+    }
+}
+
+impl<I2C, Delay, Sdb> IS31FL3731<I2C, Delay, Sdb>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin {
+    /// Creates a driver that owns the GPIO connected to the chip's SDB (hardware shutdown) pin.
+    pub fn with_sdb(i2c: I2C, delay: Delay, address: Address, sdb: Sdb) -> Self {
         Self {
             i2c,
             delay,
-            frame: 0,
+            sdb,
+            layout: Linear,
+            bank: None,
+            max_burst: ISSI_PWM_LEN,
             address,
         }
     }
+}
 
-    pub fn reset(&mut self) -> Result<(), I2cError> {
-        // shutdown
-        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_SHUTDOWN, 0x00)?;
+impl<I2C, Delay, Sdb, L, I2cError> IS31FL3731<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin {
+    /// Replaces the pixel mapping used by [`Self::set_pixel`].
+    pub fn with_layout<L2>(self, layout: L2) -> IS31FL3731<I2C, Delay, Sdb, L2> {
+        IS31FL3731 {
+            i2c: self.i2c,
+            delay: self.delay,
+            sdb: self.sdb,
+            layout,
+            bank: self.bank,
+            max_burst: self.max_burst,
+            address: self.address,
+        }
+    }
+
+    pub fn layout(&self) -> &L {
+        &self.layout
+    }
+
+    /// Gives back the bus and delay. An SDB pin is dropped; see [`Self::release_with_sdb`].
+    pub fn release(self) -> (I2C, Delay) {
+        (self.i2c, self.delay)
+    }
+
+    pub fn release_with_sdb(self) -> (I2C, Delay, Sdb) {
+        (self.i2c, self.delay, self.sdb)
+    }
+
+    /// Drives SDB high and waits for the chip to come out of hardware shutdown.
+    pub fn power_up(&mut self) -> Result<(), Error<I2cError>> {
+        self.sdb.set_high().map_err(|_| Error::Pin)?;
+        self.delay.delay_ms(SDB_WAKE_DELAY_MS);
+
+        Ok(())
+    }
+
+    /// Drives SDB low. Register contents are kept while the pin is held low.
+    pub fn power_down(&mut self) -> Result<(), Error<I2cError>> {
+        self.sdb.set_low().map_err(|_| Error::Pin)?;
+        self.bank = None;
+
+        Ok(())
+    }
+
+    /// Toggles SDB and then runs [`Self::reset`]. Without an SDB pin this is the same as `reset`.
+    pub fn hard_reset(&mut self) -> Result<(), Error<I2cError>> {
+        self.power_down()?;
+        self.delay.delay_ms(10);
+        self.power_up()?;
+        self.reset()
+    }
+
+    pub fn reset(&mut self) -> Result<(), Error<I2cError>> {
+        self.shutdown()?;
         self.delay.delay_ms(10);
+        self.wake()?;
+        self.picture_mode()?;
+
+        Ok(())
+    }
+
+    /// Enters software shutdown. Frame contents and configuration are kept.
+    pub fn shutdown(&mut self) -> Result<(), Error<I2cError>> {
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_SHUTDOWN, 0x00)?;
+
+        Ok(())
+    }
 
-        // out of shutdown
+    /// Leaves software shutdown without touching frame contents or the display mode.
+    pub fn wake(&mut self) -> Result<(), Error<I2cError>> {
         self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_SHUTDOWN, 0x01)?;
 
-        // picture mode
+        Ok(())
+    }
+
+    pub fn picture_mode(&mut self) -> Result<(), Error<I2cError>> {
         self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE)?;
+
+        Ok(())
+    }
+
+    /// Selects the frame shown in Picture Mode.
+    pub fn display_frame(&mut self, frame: Frame) -> Result<(), Error<I2cError>> {
+        self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_PICTURE_FRAME, frame.index())?;
+
+        Ok(())
+    }
+
+    /// Reads the Frame State register, which also clears the movie-finished interrupt.
+    pub fn frame_state(&mut self) -> Result<FrameState, Error<I2cError>> {
+        let data = self.read_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_FRAME_STATE)?;
+
+        Ok(FrameState::decode(data))
+    }
+
+    pub fn clear_interrupt(&mut self) -> Result<(), Error<I2cError>> {
+        self.frame_state()?;
+
+        Ok(())
+    }
+
+    /// Polls the INTB pin every millisecond until an Auto Frame Play movie started with a finite
+    /// [`Loops::Count`] finishes, then clears the interrupt.
+    pub fn wait_for_movie_end<Intb: InputPin>(&mut self, intb: &mut Intb, timeout_ms: u32) -> Result<FrameState, Error<I2cError>> {
+        for _ in 0..timeout_ms {
+            // INTB is open drain and active low
+            if intb.is_low().map_err(|_| Error::Pin)? {
+                return self.frame_state();
+            }
+            self.delay.delay_ms(1);
+        }
+
+        Err(Error::Timeout)
+    }
+
+    /// Awaits INTB going low, then reads and clears the frame state.
+    #[cfg(feature = "async")]
+    pub async fn wait_for_movie_end_async<Intb>(&mut self, intb: &mut Intb) -> Result<FrameState, Error<I2cError>>
+    where
+        Intb: embedded_hal_async::digital::Wait {
+        intb.wait_for_low().await.map_err(|_| Error::Pin)?;
+
+        self.frame_state()
+    }
+
+    /// Starts Auto Frame Play and returns the frame delay actually programmed. With a finite
+    /// [`Loops::Count`] the chip asserts INTB once the movie has finished.
+    pub fn auto_play(&mut self, config: &AutoPlay) -> Result<Duration, Error<I2cError>> {
+        let (control1, control2, frame_delay) = config.encode()?;
+
+        // Auto Play Control 1 and 2 are consecutive, so both go out in one burst
+        self.write_registers(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_AUTOPLAY_CONTROL1, &[control1, control2])?;
+        self.write(&[ISSI_REG_CONFIG, ISSI_REG_CONFIG_AUTOPLAY_MODE | config.start_frame.index()])?;
+
+        Ok(frame_delay)
+    }
+
+    /// Starts Audio Frame Play, where the audio input level picks the displayed frame.
+    pub fn audio_play(&mut self, config: &AudioPlay) -> Result<(), Error<I2cError>> {
+        let control1 = config.encode()?;
+
+        self.write_registers(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_AUTOPLAY_CONTROL1, &[control1])?;
+        self.write(&[ISSI_REG_AUDIOSYNC, config.audio_sync as u8])?;
+        self.write(&[ISSI_REG_CONFIG, ISSI_REG_CONFIG_AUDIOPLAY_MODE | config.start_frame.index()])?;
+
         Ok(())
     }
 
-    pub fn audio_sync(&mut self, enable: bool) -> Result<(), I2cError> {
+    /// Configures hardware breathing and returns the steps the durations were rounded to.
+    pub fn breath(&mut self, config: &Breath) -> Result<BreathSteps, Error<I2cError>> {
+        let (control1, control2, steps) = config.encode();
+        self.write_registers(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_BREATH_CONTROL1, &[control1, control2])?;
+
+        Ok(steps)
+    }
+
+    pub fn audio_sync(&mut self, enable: bool) -> Result<(), Error<I2cError>> {
         let data = if enable { 1 } else { 0 };
         self.write_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_AUDIOSYNC, data)?;
 
         Ok(())
     }
 
-    pub fn clear(&mut self, frame: u8) -> Result<(), I2cError> {
+    pub fn clear(&mut self, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
         self.select_bank(frame)?;
 
-        let mut erase_buf = vec![0; 25];
+        let mut erase_buf = [0; 25];
         for x in 0..6 {
             erase_buf[0] = 0x24 + x*24;
-            self.i2c.write(self.address as u8, &erase_buf)?;
+            self.write(&erase_buf)?;
         }
 
         Ok(())
     }
 
-    pub fn set_pwm_by_index(&mut self, index: usize, pwm: u8, frame: u8) -> Result<(), I2cError> {
-        if index < MATRIX_HEIGHT*MATRIX_WIDTH {
-            const PWM_OFFSET:usize = 0x24;
+    pub fn set_pwm_by_index(&mut self, index: usize, pwm: u8, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let reg = pwm_register(index)?;
+        self.write_register(frame, reg, pwm)?;
+
+        Ok(())
+    }
+
+    /// Limits how many data bytes a single bulk PWM transfer may carry (1..=144), for I2C
+    /// peripherals that cap the transfer length.
+    pub fn set_max_burst(&mut self, len: usize) -> Result<(), Error<I2cError>> {
+        self.max_burst = check_max_burst(len)?;
+
+        Ok(())
+    }
+
+    /// Uploads all 144 PWM values of a frame using auto-increment bursts.
+    pub fn write_frame(&mut self, frame: impl Into<u8>, pwm: &[u8; ISSI_PWM_LEN]) -> Result<(), Error<I2cError>> {
+        self.write_pwm(frame, 0, pwm)
+    }
 
-            self.write_register(frame, (PWM_OFFSET + index) as u8, pwm)?;
+    /// Uploads consecutive PWM values starting at LED `index`.
+    pub fn write_pwm(&mut self, frame: impl Into<u8>, index: usize, pwm: &[u8]) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        for (reg, chunk) in pwm_bursts(index, pwm, self.max_burst)? {
+            self.write_registers(frame, reg, chunk)?;
         }
 
         Ok(())
     }
 
-    fn select_bank(&mut self, bank: u8) -> Result<(), I2cError> {
-        self.i2c.write(self.address as u8, &[ISSI_COMMAND_REGISTER, bank])?;
+    pub fn set_led_enabled(&mut self, index: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.update_control_bit(ISSI_REG_LED_CONTROL, index, enable, frame)
+    }
+
+    pub fn set_row_enabled(&mut self, row: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.write_control_row(ISSI_REG_LED_CONTROL, row, enable, frame)
+    }
+
+    pub fn set_all_leds_enabled(&mut self, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let data = if enable { 0xFF } else { 0x00 };
+        self.write_led_mask(&[data; ISSI_LED_CONTROL_LEN], frame)
+    }
+
+    /// Writes all 144 LED on/off bits of a frame, LED `n` being bit `n % 8` of `mask[n / 8]`.
+    pub fn write_led_mask(&mut self, mask: &[u8; ISSI_LED_CONTROL_LEN], frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        self.write_registers(frame, ISSI_REG_LED_CONTROL, mask)?;
 
         Ok(())
     }
 
-    fn write_register(&mut self, bank: u8, reg: u8, data: u8) -> Result<(), I2cError> {
-        self.select_bank(bank)?;
-        self.i2c.write(self.address as u8, &[reg, data])?;
+    pub fn set_led_blink(&mut self, index: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.update_control_bit(ISSI_REG_BLINK_CONTROL, index, enable, frame)
+    }
+
+    pub fn set_row_blink(&mut self, row: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        self.write_control_row(ISSI_REG_BLINK_CONTROL, row, enable, frame)
+    }
+
+    /// Writes all 144 blink bits of a frame, laid out like [`Self::write_led_mask`].
+    pub fn write_blink_mask(&mut self, mask: &[u8; ISSI_LED_CONTROL_LEN], frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        self.write_registers(frame, ISSI_REG_BLINK_CONTROL, mask)?;
 
         Ok(())
     }
-}
 
-#[cfg(test)]
+    /// Enables or disables blinking of all LEDs whose blink bit is set.
+    pub fn blink(&mut self, enable: bool) -> Result<(), Error<I2cError>> {
+        let current = self.read_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_DISPLAY_OPTION)?;
+        let data = update_bits(current, ISSI_DISPLAY_OPTION_BLINK_ENABLE, enable);
+        self.write(&[ISSI_REG_DISPLAY_OPTION, data])?;
 
-mod tests {
-    use super::*;
-    use embedded_hal::delay::DelayNs;
-    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        Ok(())
+    }
 
-    struct DelayStub;
-    impl DelayNs for DelayStub {
-        fn delay_ns(&mut self, _ns: u32) {}
+    /// Sets the blink period in hardware units of 0.27 s (0..=7).
+    pub fn set_blink_period(&mut self, units: u8) -> Result<(), Error<I2cError>> {
+        check_blink_period(units)?;
+
+        let current = self.read_register(ISSI_BANK_FUNCTION_REGISTER, ISSI_REG_DISPLAY_OPTION)?;
+        let data = with_blink_period(current, units);
+        self.write(&[ISSI_REG_DISPLAY_OPTION, data])?;
+
+        Ok(())
     }
 
-    #[test]
-    fn test_reset() {
-        let mut i2c = Mock::new(&[
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_REG_SHUTDOWN, 0x00]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_REG_SHUTDOWN, 0x01]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE]),
-        ]);
+    /// Sets the blink period to the hardware step closest to `period` and returns the period
+    /// actually programmed.
+    pub fn set_blink_period_duration(&mut self, period: Duration) -> Result<Duration, Error<I2cError>> {
+        let (units, period) = blink_period_units(period)?;
+        self.set_blink_period(units)?;
 
-        let mut sut = IS31FL3731 {
-            i2c: i2c.clone(),
-            delay: DelayStub{},
-            frame: 0,
-            address: Address::GND,
-        };
+        Ok(period)
+    }
 
-        sut.reset().unwrap();
-        i2c.done();
+    /// Reads one register of the function bank (0x00..=0x0C).
+    pub fn read_function_register(&mut self, reg: u8) -> Result<u8, Error<I2cError>> {
+        check_function_register(reg)?;
+
+        self.read_register(ISSI_BANK_FUNCTION_REGISTER, reg)
     }
 
-    #[test]
-    fn test_clear() {
-        const FRAME:u8 = 3;
-        let mut i2c = Mock::new(&[
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, FRAME]),
-            Transaction::write(Address::GND as u8,
-                               vec![36, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
-            Transaction::write(Address::GND as u8,
-                               vec![60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
-            Transaction::write(Address::GND as u8,
-                               vec![84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
-            Transaction::write(Address::GND as u8,
-                               vec![108, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
-            Transaction::write(Address::GND as u8,
-                               vec![132, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
-            Transaction::write(Address::GND as u8,
-                               vec![156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
-        ]);
+    pub fn read_pwm(&mut self, frame: impl Into<u8>) -> Result<[u8; ISSI_PWM_LEN], Error<I2cError>> {
+        let mut pwm = [0; ISSI_PWM_LEN];
+        let frame = check_frame(frame)?;
+        self.read_registers(frame, ISSI_REG_PWM, &mut pwm)?;
 
-        let mut sut = IS31FL3731 {
-            i2c: i2c.clone(),
-            delay: DelayStub{},
-            frame: 0,
-            address: Address::GND,
-        };
+        Ok(pwm)
+    }
 
-        sut.clear(FRAME).unwrap();
-        i2c.done();
+    /// Reads the LED on/off bits of a frame, laid out like [`Self::write_led_mask`].
+    pub fn read_led_mask(&mut self, frame: impl Into<u8>) -> Result<[u8; ISSI_LED_CONTROL_LEN], Error<I2cError>> {
+        let mut mask = [0; ISSI_LED_CONTROL_LEN];
+        let frame = check_frame(frame)?;
+        self.read_registers(frame, ISSI_REG_LED_CONTROL, &mut mask)?;
+
+        Ok(mask)
     }
 
-    #[test]
-    fn test_audio_sync_false() {
-        let mut i2c = Mock::new(&[
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_REG_AUDIOSYNC, 0]),
-        ]);
+    /// Reads the blink bits of a frame, laid out like [`Self::write_blink_mask`].
+    pub fn read_blink_mask(&mut self, frame: impl Into<u8>) -> Result<[u8; ISSI_LED_CONTROL_LEN], Error<I2cError>> {
+        let mut mask = [0; ISSI_LED_CONTROL_LEN];
+        let frame = check_frame(frame)?;
+        self.read_registers(frame, ISSI_REG_BLINK_CONTROL, &mut mask)?;
 
-        let mut sut = IS31FL3731 {
-            i2c: i2c.clone(),
-            delay: DelayStub{},
-            frame: 0,
-            address: Address::GND,
-        };
+        Ok(mask)
+    }
 
-        sut.audio_sync(false).unwrap();
-        i2c.done();
+    fn update_control_bit(&mut self, base: u8, index: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let (reg, bit) = control_bit(base, index)?;
+        let current = self.read_register(frame, reg)?;
+        self.write(&[reg, update_bits(current, bit, enable)])?;
+
+        Ok(())
     }
 
-    #[test]
-    fn test_audio_sync_true() {
-        let mut i2c = Mock::new(&[
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_REG_AUDIOSYNC, 1]),
-        ]);
+    fn write_control_row(&mut self, base: u8, row: usize, enable: bool, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let (reg, data) = control_row(base, row, enable)?;
+        self.write_registers(frame, reg, &data)?;
 
-        let mut sut = IS31FL3731 {
-            i2c: i2c.clone(),
-            delay: DelayStub{},
-            frame: 0,
-            address: Address::GND,
-        };
+        Ok(())
+    }
 
-        sut.audio_sync(true).unwrap();
-        i2c.done();
+    fn select_bank(&mut self, bank: u8) -> Result<(), Error<I2cError>> {
+        if self.bank != Some(bank) {
+            self.write(&[ISSI_COMMAND_REGISTER, bank])?;
+            self.bank = Some(bank);
+        }
+
+        Ok(())
     }
 
-    #[test]
-    fn test_set_pwm_by_index() {
-        let mut i2c = Mock::new(&[
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, 4]),
-            Transaction::write(Address::GND as u8,
-                               vec![0x24, 128]),
-            Transaction::write(Address::GND as u8,
-                               vec![ISSI_COMMAND_REGISTER, 5]),
-            Transaction::write(Address::GND as u8,
-                               vec![0x24 + 50, 255]),
-        ]);
+    fn write(&mut self, data: &[u8]) -> Result<(), Error<I2cError>> {
+        // after a failed transfer we cannot tell which bank the chip ended up on
+        self.i2c.write(self.address as u8, data).inspect_err(|_| self.bank = None)?;
 
-        let mut sut = IS31FL3731 {
-            i2c: i2c.clone(),
-            delay: DelayStub{},
-            frame: 0,
-            address: Address::GND,
-        };
+        Ok(())
+    }
 
-        sut.set_pwm_by_index(0, 128, 4).unwrap();
-        sut.set_pwm_by_index(50, 255, 5).unwrap();
-        i2c.done();
+    fn write_read(&mut self, write: &[u8], read: &mut [u8]) -> Result<(), Error<I2cError>> {
+        self.i2c.write_read(self.address as u8, write, read).inspect_err(|_| self.bank = None)?;
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    fn write_register(&mut self, bank: u8, reg: u8, data: u8) -> Result<(), Error<I2cError>> {
+        self.select_bank(bank)?;
+        self.write(&[reg, data])?;
+
+        Ok(())
+    }
+
+    fn write_registers(&mut self, bank: u8, reg: u8, data: &[u8]) -> Result<(), Error<I2cError>> {
+        let mut buf = [0; ISSI_PWM_LEN + 1];
+        buf[0] = reg;
+        buf[1..=data.len()].copy_from_slice(data);
+
+        self.select_bank(bank)?;
+        self.write(&buf[..=data.len()])?;
+
+        Ok(())
+    }
+
+    fn read_register(&mut self, bank: u8, reg: u8) -> Result<u8, Error<I2cError>> {
+        let mut data = [0];
+        self.read_registers(bank, reg, &mut data)?;
+
+        Ok(data[0])
+    }
+
+    fn read_registers(&mut self, bank: u8, reg: u8, data: &mut [u8]) -> Result<(), Error<I2cError>> {
+        self.select_bank(bank)?;
+        self.write_read(&[reg], data)?;
+
+        Ok(())
+    }
+}
+
+impl<I2C, Delay, Sdb, L, I2cError> IS31FL3731<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin,
+    L: Layout {
+    /// Sets the PWM value of the logical pixel `(x, y)` as mapped by the driver's [`Layout`].
+    pub fn set_pixel(&mut self, x: usize, y: usize, pwm: u8, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let index = self.layout.index(x, y).ok_or(Error::InvalidCoordinate)?;
+
+        self.set_pwm_by_index(index, pwm, frame)
+    }
+
+    /// Lights the pixels of `text` in the built-in 5×7 font, its top-left corner at the logical
+    /// pixel `(x, y)`, and returns the text's width in pixels. Other pixels of `frame` are left
+    /// as they are and glyph pixels outside the layout are clipped. Each pixel is a separate
+    /// write; draw into a [`FrameBuffer`] and flush it to update a whole frame at once.
+    pub fn draw_text(&mut self, frame: impl Into<u8>, x: i32, y: i32, text: &str, brightness: u8) -> Result<usize, Error<I2cError>> {
+        let frame = check_frame(frame)?;
+        let (width, height) = (self.layout.width(), self.layout.height());
+
+        for (px, py) in font::pixels(text, x, y, width, height) {
+            self.set_pixel(px, py, brightness, frame)?;
+        }
+
+        Ok(font::text_width(text))
+    }
+}
+
+impl<I2C, Delay, Sdb, L, I2cError> IS31FL3731<I2C, Delay, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin,
+    L: RgbLayout {
+    /// Sets the red, green and blue PWM values of the logical pixel `(x, y)` of an RGB board.
+    pub fn set_pixel_rgb(&mut self, x: usize, y: usize, rgb: [u8; 3], frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = frame.into();
+        let indices = self.layout.rgb_index(x, y).ok_or(Error::InvalidCoordinate)?;
+
+        for (index, pwm) in indices.into_iter().zip(rgb) {
+            self.set_pwm_by_index(index, pwm, frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Register encoding shared by the blocking and async drivers, which only differ in how they
+// talk to the bus.
+
+fn check_frame<E>(frame: impl Into<u8>) -> Result<u8, Error<E>> {
+    let frame = frame.into();
+    if frame < FRAME_COUNT {
+        Ok(frame)
+    } else {
+        Err(Error::InvalidFrame)
+    }
+}
+
+fn check_max_burst<E>(len: usize) -> Result<usize, Error<E>> {
+    if len == 0 {
+        return Err(Error::InvalidConfig);
+    }
+
+    Ok(len.min(ISSI_PWM_LEN))
+}
+
+fn check_function_register<E>(reg: u8) -> Result<(), Error<E>> {
+    if reg > ISSI_REG_AUDIO_ADC_RATE {
+        return Err(Error::InvalidConfig);
+    }
+
+    Ok(())
+}
+
+fn pwm_register<E>(index: usize) -> Result<u8, Error<E>> {
+    if index >= ISSI_PWM_LEN {
+        return Err(Error::IndexOutOfRange);
+    }
+
+    Ok(ISSI_REG_PWM + index as u8)
+}
+
+/// Splits PWM values starting at LED `index` into bursts of at most `max_burst` bytes, each
+/// paired with the register it starts at.
+fn pwm_bursts<E>(index: usize, pwm: &[u8], max_burst: usize) -> Result<impl Iterator<Item = (u8, &[u8])>, Error<E>> {
+    index.checked_add(pwm.len())
+        .filter(|&end| end <= ISSI_PWM_LEN)
+        .ok_or(Error::IndexOutOfRange)?;
+
+    Ok(pwm.chunks(max_burst)
+        .enumerate()
+        .map(move |(n, chunk)| (ISSI_REG_PWM + (index + n * max_burst) as u8, chunk)))
+}
+
+/// Returns the LED or blink control register holding LED `index` and its bit mask.
+fn control_bit<E>(base: u8, index: usize) -> Result<(u8, u8), Error<E>> {
+    if index >= ISSI_PWM_LEN {
+        return Err(Error::IndexOutOfRange);
+    }
+
+    Ok((base + (index / 8) as u8, 1 << (index % 8)))
+}
+
+/// Returns the first LED or blink control register of `row` and the bytes that set the row.
+fn control_row<E>(base: u8, row: usize, enable: bool) -> Result<(u8, [u8; MATRIX_WIDTH / 8]), Error<E>> {
+    if row >= MATRIX_HEIGHT {
+        return Err(Error::InvalidCoordinate);
+    }
+
+    // each row of 16 LEDs is covered by two consecutive control bytes
+    let data = if enable { 0xFF } else { 0x00 };
+    Ok((base + (row * MATRIX_WIDTH / 8) as u8, [data; MATRIX_WIDTH / 8]))
+}
+
+fn update_bits(current: u8, mask: u8, enable: bool) -> u8 {
+    if enable { current | mask } else { current & !mask }
+}
+
+fn check_blink_period<E>(units: u8) -> Result<(), Error<E>> {
+    if units > ISSI_DISPLAY_OPTION_BLINK_PERIOD_MASK {
+        return Err(Error::InvalidConfig);
+    }
+
+    Ok(())
+}
+
+fn with_blink_period(display_option: u8, units: u8) -> u8 {
+    (display_option & !ISSI_DISPLAY_OPTION_BLINK_PERIOD_MASK) | units
+}
+
+/// Rounds `period` to the nearest blink period step and returns it with the period it encodes.
+fn blink_period_units<E>(period: Duration) -> Result<(u8, Duration), Error<E>> {
+    let units = (period.as_millis() + BLINK_PERIOD_UNIT_MS as u128 / 2) / BLINK_PERIOD_UNIT_MS as u128;
+    if units > ISSI_DISPLAY_OPTION_BLINK_PERIOD_MASK as u128 {
+        return Err(Error::InvalidConfig);
+    }
+
+    Ok((units as u8, Duration::from_millis(units as u64 * BLINK_PERIOD_UNIT_MS)))
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    extern crate std;
+
+    pub(crate) use std::io::ErrorKind as IoErrorKind;
+    pub(crate) use std::vec;
+    pub(crate) use std::vec::Vec;
+
+    /// Delay that returns immediately, for both the blocking and async drivers.
+    pub(crate) struct DelayStub;
+
+    impl embedded_hal::delay::DelayNs for DelayStub {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[cfg(feature = "async")]
+    impl embedded_hal_async::delay::DelayNs for DelayStub {
+        async fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    /// Polls `future` to completion; the mocks never return `Pending` for long.
+    #[cfg(feature = "async")]
+    pub(crate) fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        let mut future = core::pin::pin!(future);
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        loop {
+            if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{vec, DelayStub, Vec};
+    use embedded_hal_mock::eh1::digital::{Mock as PinMock, State, Transaction as PinTransaction};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    /// Mirrors the matrix horizontally.
+    struct Flipped;
+
+    impl Layout for Flipped {
+        fn width(&self) -> usize { 16 }
+        fn height(&self) -> usize { 9 }
+        fn index(&self, x: usize, y: usize) -> Option<usize> {
+            Linear.index(15 - x.min(15), y)
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x01]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.reset().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_clear() {
+        const FRAME:u8 = 3;
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, FRAME]),
+            Transaction::write(Address::GND as u8,
+                               vec![36, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![84, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![108, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![132, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![156, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.clear(FRAME).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_audio_sync_false() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 0]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.audio_sync(false).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_audio_sync_true() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 1]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.audio_sync(true).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pwm_by_index() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 4]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 128]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 5]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 50, 255]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_pwm_by_index(0, 128, 4).unwrap();
+        sut.set_pwm_by_index(50, 255, Frame::new(5).unwrap()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pwm_by_index_out_of_range() {
+        let mut i2c = Mock::new(&[]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.set_pwm_by_index(144, 255, 0), Err(Error::IndexOutOfRange));
+        assert_eq!(sut.set_pwm_by_index(0, 255, 8), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_clear_invalid_frame() {
+        let mut i2c = Mock::new(&[]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.clear(ISSI_BANK_FUNCTION_REGISTER), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_error_kind() {
+        use embedded_hal::i2c::{Error as _, ErrorKind, NoAcknowledgeSource};
+
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER])
+                .with_error(nack),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let error = sut.audio_sync(true).unwrap_err();
+        assert_eq!(error, Error::I2c(nack));
+        assert_eq!(error.kind(), nack);
+        assert_eq!(Error::<ErrorKind>::InvalidFrame.kind(), ErrorKind::Other);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_led_enabled() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x01], vec![0b0000_0001]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x01, 0b0000_1001]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x11], vec![0xFF]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x11, 0x7F]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_led_enabled(11, true, 2).unwrap();
+        sut.set_led_enabled(143, false, 2).unwrap();
+        assert_eq!(sut.set_led_enabled(144, true, 2), Err(Error::IndexOutOfRange));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_row_enabled() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x04, 0xFF, 0xFF]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x10, 0x00, 0x00]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_row_enabled(2, true, 1).unwrap();
+        sut.set_row_enabled(8, false, 1).unwrap();
+        assert_eq!(sut.set_row_enabled(9, true, 1), Err(Error::InvalidCoordinate));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_all_leds_enabled() {
+        let mut expected = vec![0x00];
+        expected.extend_from_slice(&[0xFF; 18]);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 7]),
+            Transaction::write(Address::GND as u8,
+                               expected),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_all_leds_enabled(true, 7).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_led_mask() {
+        let mut mask = [0u8; 18];
+        mask[0] = 0b1010_0101;
+        mask[17] = 0x80;
+        let mut expected = vec![0x00];
+        expected.extend_from_slice(&mask);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::GND as u8,
+                               expected),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.write_led_mask(&mask, 0).unwrap();
+        assert_eq!(sut.write_led_mask(&mask, 8), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_led_blink() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 3]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x12 + 2], vec![0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x12 + 2, 0b0010_0000]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_led_blink(21, true, 3).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_row_blink() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x12 + 6, 0xFF, 0xFF]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_row_blink(3, true, 0).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_blink_mask() {
+        let mask = [0x55u8; 18];
+        let mut expected = vec![0x12];
+        expected.extend_from_slice(&mask);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 6]),
+            Transaction::write(Address::GND as u8,
+                               expected),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.write_blink_mask(&mask, 6).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_blink() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x23]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x2B]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x2B]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x23]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.blink(true).unwrap();
+        sut.blink(false).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_blink_period() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x09]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x0E]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_DISPLAY_OPTION], vec![0x0E]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_DISPLAY_OPTION, 0x0A]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_blink_period(6).unwrap();
+        assert_eq!(sut.set_blink_period_duration(Duration::from_millis(600)), Ok(Duration::from_millis(540)));
+        assert_eq!(sut.set_blink_period(8), Err(Error::InvalidConfig));
+        assert_eq!(sut.set_blink_period_duration(Duration::from_secs(3)), Err(Error::InvalidConfig));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_picture_mode() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.picture_mode().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_auto_play() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUTOPLAY_CONTROL1, 0x24, 10]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, 0x08 | 2]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let config = AutoPlay {
+            start_frame: Frame::new(2).unwrap(),
+            frame_count: 4,
+            loops: Loops::Count(2),
+            frame_delay: Duration::from_millis(108),
+        };
+        assert_eq!(sut.auto_play(&config), Ok(Duration::from_millis(110)));
+        assert_eq!(sut.auto_play(&AutoPlay { loops: Loops::Count(8), ..config }), Err(Error::InvalidConfig));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_audio_play() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUTOPLAY_CONTROL1, 6]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, 0x10 | 1]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let config = AudioPlay {
+            start_frame: Frame::new(1).unwrap(),
+            frame_count: 6,
+            audio_sync: true,
+        };
+        sut.audio_play(&config).unwrap();
+        assert_eq!(sut.audio_play(&AudioPlay { frame_count: 0, ..config }), Err(Error::InvalidConfig));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_breath() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_BREATH_CONTROL1, 0x55, 0x12]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let config = Breath {
+            fade_in: Duration::from_millis(800),
+            fade_out: Duration::from_millis(850),
+            extinguish: Duration::from_millis(14),
+            enable: true,
+        };
+        let steps = sut.breath(&config).unwrap();
+        assert_eq!(steps, BreathSteps { fade_in: 5, fade_out: 5, extinguish: 2 });
+        i2c.done();
+    }
+
+    #[test]
+    fn test_display_frame() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_PICTURE_FRAME, 5]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.display_frame(Frame::new(5).unwrap()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_shutdown_and_wake() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x01]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.shutdown().unwrap();
+        sut.wake().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_hard_reset() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x00]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_SHUTDOWN, 0x01]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_CONFIG, ISSI_REG_CONFIG_PICTURE_MODE]),
+        ]);
+        let mut sdb = PinMock::new(&[
+            PinTransaction::set(State::Low),
+            PinTransaction::set(State::High),
+        ]);
+
+        let mut sut = IS31FL3731::with_sdb(i2c.clone(), DelayStub{}, Address::GND, sdb.clone());
+
+        sut.hard_reset().unwrap();
+        i2c.done();
+        sdb.done();
+    }
+
+    #[test]
+    fn test_power_up_pin_error() {
+        use crate::test_util::IoErrorKind;
+        use embedded_hal_mock::eh1::MockError;
+
+        let mut i2c = Mock::new(&[]);
+        let mut sdb = PinMock::new(&[
+            PinTransaction::set(State::High).with_error(MockError::Io(IoErrorKind::Other)),
+        ]);
+
+        let mut sut = IS31FL3731::with_sdb(i2c.clone(), DelayStub{}, Address::GND, sdb.clone());
+
+        assert_eq!(sut.power_up(), Err(Error::Pin));
+        i2c.done();
+        sdb.done();
+    }
+
+    #[test]
+    fn test_frame_state() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x12]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x02]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let state = sut.frame_state().unwrap();
+        assert_eq!(state.frame, Frame::new(2).unwrap());
+        assert!(state.movie_finished);
+        sut.clear_interrupt().unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_wait_for_movie_end() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x17]),
+        ]);
+        let mut intb = PinMock::new(&[
+            PinTransaction::get(State::High),
+            PinTransaction::get(State::High),
+            PinTransaction::get(State::Low),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let state = sut.wait_for_movie_end(&mut intb, 10).unwrap();
+        assert_eq!(state, FrameState { frame: Frame::new(7).unwrap(), movie_finished: true });
+        i2c.done();
+        intb.done();
+    }
+
+    #[test]
+    fn test_wait_for_movie_end_timeout() {
+        let mut i2c = Mock::new(&[]);
+        let mut intb = PinMock::new(&[
+            PinTransaction::get(State::High),
+            PinTransaction::get(State::High),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.wait_for_movie_end(&mut intb, 2), Err(Error::Timeout));
+        i2c.done();
+        intb.done();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_wait_for_movie_end_async() {
+        use crate::test_util::block_on;
+
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_FRAME_STATE], vec![0x10]),
+        ]);
+        let mut intb = PinMock::new(&[
+            PinTransaction::wait_for_state(State::Low),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        let state = block_on(sut.wait_for_movie_end_async(&mut intb)).unwrap();
+        assert!(state.movie_finished);
+        i2c.done();
+        intb.done();
+    }
+
+    #[test]
+    fn test_read_function_register() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![ISSI_REG_SHUTDOWN], vec![0x01]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.read_function_register(ISSI_REG_SHUTDOWN), Ok(0x01));
+        assert_eq!(sut.read_function_register(0x0D), Err(Error::InvalidConfig));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_pwm() {
+        let pwm: Vec<u8> = (0..144).map(|i| i as u8).collect();
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 4]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x24], pwm.clone()),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.read_pwm(4).unwrap().as_slice(), pwm.as_slice());
+        assert_eq!(sut.read_pwm(8), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_led_and_blink_mask() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 1]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x00], vec![0xFF; 18]),
+            Transaction::write_read(Address::GND as u8,
+                                    vec![0x12], vec![0x0F; 18]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.read_led_mask(1), Ok([0xFF; 18]));
+        assert_eq!(sut.read_blink_mask(1), Ok([0x0F; 18]));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_bank_selection_is_cached() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x25, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x26, 3]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.set_pwm_by_index(0, 1, 2).unwrap();
+        sut.set_pwm_by_index(1, 2, 2).unwrap();
+        sut.set_pwm_by_index(2, 3, 2).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_bank_selection_invalidated_on_error() {
+        use embedded_hal::i2c::ErrorKind;
+
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 1])
+                .with_error(ErrorKind::Bus),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 1]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.set_pwm_by_index(0, 1, 2), Err(Error::I2c(ErrorKind::Bus)));
+        sut.set_pwm_by_index(0, 1, 2).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_frame() {
+        let pwm: Vec<u8> = (0..144).map(|i| i as u8).collect();
+        let mut expected = vec![0x24];
+        expected.extend_from_slice(&pwm);
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 3]),
+            Transaction::write(Address::GND as u8,
+                               expected),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        sut.write_frame(3, pwm.as_slice().try_into().unwrap()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_write_pwm_with_max_burst() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 10, 1, 2, 3, 4]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 14, 5, 6, 7, 8]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 18, 9]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        assert_eq!(sut.set_max_burst(0), Err(Error::InvalidConfig));
+        sut.set_max_burst(4).unwrap();
+        sut.write_pwm(0, 10, &[1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert_eq!(sut.write_pwm(0, 140, &[0; 5]), Err(Error::IndexOutOfRange));
+        assert_eq!(sut.write_pwm(0, usize::MAX, &[0; 2]), Err(Error::IndexOutOfRange));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pixel() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 16 + 2, 10]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 16 + 13, 20]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+        sut.set_pixel(2, 1, 10, 0).unwrap();
+        assert_eq!(sut.set_pixel(2, 9, 10, 0), Err(Error::InvalidCoordinate));
+
+        let mut sut = sut.with_layout(Flipped);
+        sut.set_pixel(2, 1, 20, 0).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_set_pixel_rgb() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 117, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 68, 2]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 101, 3]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND)
+            .with_layout(boards::PimoroniLedShim);
+
+        sut.set_pixel_rgb(1, 0, [1, 2, 3], 1).unwrap();
+        assert_eq!(sut.set_pixel_rgb(28, 0, [1, 2, 3], 1), Err(Error::InvalidCoordinate));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_release() {
+        let i2c = Mock::new(&[
+            Transaction::write(0x68, vec![0x6B, 0x00]),
+        ]);
+
+        let sut = IS31FL3731::new(i2c, DelayStub{}, Address::GND);
+
+        let (mut i2c, _delay) = sut.release();
+        i2c.write(0x68, &[0x6B, 0x00]).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_shared_bus_refcell_device() {
+        use core::cell::RefCell;
+        use embedded_hal_bus::i2c::RefCellDevice;
+
+        let i2c = RefCell::new(Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 1]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24, 10]),
+            Transaction::write(Address::VCC as u8,
+                               vec![ISSI_COMMAND_REGISTER, 2]),
+            Transaction::write(Address::VCC as u8,
+                               vec![0x24, 20]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x25, 11]),
+            Transaction::write(Address::VCC as u8,
+                               vec![0x25, 21]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_REG_PICTURE_FRAME, 1]),
+            Transaction::write(Address::VCC as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::VCC as u8,
+                               vec![ISSI_REG_PICTURE_FRAME, 2]),
+        ]));
+
+        let mut left = IS31FL3731::new(RefCellDevice::new(&i2c), DelayStub{}, Address::GND);
+        let mut right = IS31FL3731::new(RefCellDevice::new(&i2c), DelayStub{}, Address::VCC);
+
+        left.set_pwm_by_index(0, 10, 1).unwrap();
+        right.set_pwm_by_index(0, 20, 2).unwrap();
+        left.set_pwm_by_index(1, 11, 1).unwrap();
+        right.set_pwm_by_index(1, 21, 2).unwrap();
+        left.display_frame(Frame::new(1).unwrap()).unwrap();
+        right.display_frame(Frame::new(2).unwrap()).unwrap();
+
+        left.release();
+        right.release();
+        i2c.into_inner().done();
+    }
+
+    #[test]
+    fn test_shared_bus_critical_section_device() {
+        use core::cell::RefCell;
+        use critical_section::Mutex;
+        use embedded_hal_bus::i2c::CriticalSectionDevice;
+
+        let i2c = Mutex::new(RefCell::new(Mock::new(&[
+            Transaction::write(Address::SCL as u8,
+                               vec![ISSI_COMMAND_REGISTER, ISSI_BANK_FUNCTION_REGISTER]),
+            Transaction::write(Address::SCL as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 1]),
+            Transaction::write(Address::SDA as u8,
+                               vec![ISSI_COMMAND_REGISTER, 0]),
+            Transaction::write(Address::SDA as u8,
+                               vec![0x24 + 5, 99]),
+            Transaction::write(Address::SCL as u8,
+                               vec![ISSI_REG_AUDIOSYNC, 0]),
+        ])));
+
+        let mut first = IS31FL3731::new(CriticalSectionDevice::new(&i2c), DelayStub{}, Address::SCL);
+        let mut second = IS31FL3731::new(CriticalSectionDevice::new(&i2c), DelayStub{}, Address::SDA);
+
+        first.audio_sync(true).unwrap();
+        second.set_pwm_by_index(5, 99, 0).unwrap();
+        first.audio_sync(false).unwrap();
+
+        first.release();
+        second.release();
+        i2c.into_inner().into_inner().done();
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8,
+                               vec![ISSI_COMMAND_REGISTER, 6]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 2 * 16 + 12, 40]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 3 * 16 + 13, 40]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 4 * 16 + 14, 40]),
+            Transaction::write(Address::GND as u8,
+                               vec![0x24 + 2 * 16, 40]),
+        ]);
+
+        let mut sut = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+
+        // only the glyph's pixels are written, the rest of the frame is untouched
+        assert_eq!(sut.draw_text(6, 11, 2, "`", 40), Ok(5));
+
+        // the glyph's remaining pixels fall off the right edge
+        let mut sut = sut.with_layout(Flipped);
+        assert_eq!(sut.draw_text(6, 14, 2, "`", 40), Ok(5));
+        assert_eq!(sut.draw_text(8, 0, 0, "A", 40), Err(Error::InvalidFrame));
+        i2c.done();
+    }
+}
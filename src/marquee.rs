@@ -0,0 +1,277 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::I2c;
+
+use crate::{font, DoubleBuffer, Error, FrameBuffer, Linear, MATRIX_WIDTH};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Text enters at the right edge and leaves at the left.
+    #[default]
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarqueeConfig {
+    pub direction: Direction,
+    /// Blank pixels between the end of the text and its next repetition.
+    pub gap: usize,
+    /// How many times the text crosses the display, `None` to scroll forever.
+    pub loops: Option<u32>,
+    /// Number of [`Marquee::tick`] calls per one pixel step; larger is slower.
+    pub ticks_per_step: u32,
+    /// Row of the top of the 7 pixel high glyphs.
+    pub y: i32,
+    pub brightness: u8,
+}
+
+impl Default for MarqueeConfig {
+    fn default() -> Self {
+        Self {
+            direction: Direction::Left,
+            gap: 4,
+            loops: None,
+            ticks_per_step: 1,
+            y: 1,
+            brightness: 255,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarqueeState {
+    /// Nothing was drawn on this tick.
+    Waiting,
+    /// The next step was drawn and shown.
+    Stepped,
+    /// The last loop has left the display.
+    Finished,
+}
+
+/// Scrolls text of any length across the matrix, one pixel per step.
+///
+/// Each step is drawn into the back frame of a [`DoubleBuffer`] and then flipped onto the
+/// display, so the scrolling does not tear.
+pub struct Marquee<'a> {
+    text: &'a str,
+    config: MarqueeConfig,
+    width: usize,
+    offset: usize,
+    ticks: u32,
+    finished: bool,
+}
+
+impl<'a> Marquee<'a> {
+    pub fn new(text: &'a str, config: MarqueeConfig) -> Self {
+        Self {
+            text,
+            config,
+            width: font::text_width(text),
+            offset: 0,
+            ticks: 0,
+            finished: false,
+        }
+    }
+
+    /// Starts scrolling from the beginning again.
+    pub fn restart(&mut self) {
+        self.offset = 0;
+        self.ticks = 0;
+        self.finished = false;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advances the marquee without blocking; call it at a steady rate from the main loop.
+    /// Frames are rendered in the chip's native LED order, so only [`Linear`] displays are
+    /// accepted.
+    pub fn tick<I2C, Delay, Sdb, I2cError>(&mut self, display: &mut DoubleBuffer<I2C, Delay, Sdb, Linear>) -> Result<MarqueeState, Error<I2cError>>
+    where
+        I2C: I2c<Error = I2cError>,
+        Delay: DelayNs,
+        Sdb: OutputPin {
+        if self.finished {
+            return Ok(MarqueeState::Finished);
+        }
+
+        self.ticks += 1;
+        if self.ticks < self.config.ticks_per_step.max(1) {
+            return Ok(MarqueeState::Waiting);
+        }
+        self.ticks = 0;
+
+        let mut buffer = FrameBuffer::new();
+        let finished = self.render(&mut buffer);
+        display.write_frame(buffer.pwm())?;
+        display.flip()?;
+
+        self.advance();
+        if finished {
+            self.finished = true;
+            return Ok(MarqueeState::Finished);
+        }
+
+        Ok(MarqueeState::Stepped)
+    }
+
+    fn period(&self) -> usize {
+        (self.width + self.config.gap).max(1)
+    }
+
+    /// Draws the current step and returns whether the last loop has fully left the display.
+    fn render(&self, buffer: &mut FrameBuffer) -> bool {
+        let width = self.width as i32;
+        let period = self.period() as i32;
+        let offset = self.offset as i32;
+        let display = MATRIX_WIDTH as i32;
+        if self.config.loops == Some(0) {
+            return true;
+        }
+
+        let mut copy = 0;
+        loop {
+            if self.config.loops.is_some_and(|loops| copy >= loops) {
+                // the previous copy was the last one
+                let last = self.position(copy as i32 - 1, width, period, offset, display);
+                return last + width <= 0 || last >= display;
+            }
+
+            let x = self.position(copy as i32, width, period, offset, display);
+            let entered = match self.config.direction {
+                Direction::Left => x < display,
+                Direction::Right => x + width > 0,
+            };
+            if !entered {
+                return false;
+            }
+            if x + width > 0 && x < display {
+                buffer.draw_text(x, self.config.y, self.text, self.config.brightness);
+            }
+
+            copy += 1;
+        }
+    }
+
+    /// Left edge of repetition `copy` of the text.
+    fn position(&self, copy: i32, width: i32, period: i32, offset: i32, display: i32) -> i32 {
+        match self.config.direction {
+            Direction::Left => display - 1 - offset + copy * period,
+            Direction::Right => 1 - width + offset - copy * period,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.offset += 1;
+
+        // an endless marquee looks the same one period later once the first copy is gone
+        let period = self.period();
+        if self.config.loops.is_none() && self.offset >= MATRIX_WIDTH + self.width + period {
+            self.offset -= period;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Frame, IS31FL3731};
+    use crate::test_util::{vec, DelayStub, Vec};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    fn lit_columns(marquee: &Marquee) -> Vec<usize> {
+        let mut buffer = FrameBuffer::new();
+        marquee.render(&mut buffer);
+        (0..16).filter(|&x| (0..9).any(|y| buffer.get_pixel(x, y) != Some(0))).collect()
+    }
+
+    #[test]
+    fn test_scroll_left_once() {
+        let config = MarqueeConfig { loops: Some(1), gap: 0, ..MarqueeConfig::default() };
+        let mut sut = Marquee::new("I", config);
+
+        // 'I' has blank first and last columns
+        assert_eq!(lit_columns(&sut), vec![]);
+        sut.advance();
+        assert_eq!(lit_columns(&sut), vec![15]);
+        for _ in 0..10 {
+            sut.advance();
+        }
+        assert_eq!(lit_columns(&sut), vec![5, 6, 7]);
+
+        let mut buffer = FrameBuffer::new();
+        for _ in 0..8 {
+            sut.advance();
+        }
+        assert!(!sut.render(&mut buffer));
+        sut.advance();
+        assert!(sut.render(&mut buffer));
+    }
+
+    #[test]
+    fn test_scroll_right_with_gap() {
+        let config = MarqueeConfig { direction: Direction::Right, gap: 3, ..MarqueeConfig::default() };
+        let mut sut = Marquee::new("I", config);
+
+        sut.advance();
+        assert_eq!(lit_columns(&sut), vec![0]);
+        for _ in 0..8 {
+            sut.advance();
+        }
+        // the next repetition follows 3 blank pixels after the first one
+        assert_eq!(lit_columns(&sut), vec![0, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_endless_offset_wraps() {
+        let mut sut = Marquee::new("Hi", MarqueeConfig::default());
+
+        for _ in 0..1000 {
+            sut.advance();
+        }
+        assert!(sut.offset < MATRIX_WIDTH + sut.width + sut.period());
+        assert!(!lit_columns(&sut).is_empty());
+    }
+
+    #[test]
+    fn test_tick() {
+        let config = MarqueeConfig { loops: Some(1), ticks_per_step: 2, ..MarqueeConfig::default() };
+        let mut sut = Marquee::new("I", config);
+
+        let mut expected = vec![
+            Transaction::write(Address::GND as u8, vec![0xFD, 0x0B]),
+            Transaction::write(Address::GND as u8, vec![0x01, 0]),
+        ];
+        let mut back = 1;
+        let mut preview = Marquee::new("I", config);
+        for _ in 0..21 {
+            let mut buffer = FrameBuffer::new();
+            preview.render(&mut buffer);
+            preview.advance();
+
+            let mut data = vec![0x24];
+            data.extend_from_slice(buffer.pwm());
+            expected.push(Transaction::write(Address::GND as u8, vec![0xFD, back]));
+            expected.push(Transaction::write(Address::GND as u8, data));
+            expected.push(Transaction::write(Address::GND as u8, vec![0xFD, 0x0B]));
+            expected.push(Transaction::write(Address::GND as u8, vec![0x01, back]));
+            back ^= 1;
+        }
+        let mut i2c = Mock::new(&expected);
+
+        let driver = IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND);
+        let mut display = DoubleBuffer::new(driver, Frame::new(0).unwrap(), Frame::new(1).unwrap()).unwrap();
+
+        for _ in 0..20 {
+            assert_eq!(sut.tick(&mut display), Ok(MarqueeState::Waiting));
+            assert_eq!(sut.tick(&mut display), Ok(MarqueeState::Stepped));
+        }
+        assert_eq!(sut.tick(&mut display), Ok(MarqueeState::Waiting));
+        assert_eq!(sut.tick(&mut display), Ok(MarqueeState::Finished));
+        assert_eq!(sut.tick(&mut display), Ok(MarqueeState::Finished));
+        assert!(sut.is_finished());
+        i2c.done();
+    }
+}
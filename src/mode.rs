@@ -0,0 +1,137 @@
+use core::time::Duration;
+
+use crate::{Error, Frame, FRAME_COUNT};
+
+const FRAME_DELAY_UNIT_MS:u64 = 11;
+const FRAME_DELAY_MAX_UNITS:u64 = 64;
+const LOOPS_MAX:u8 = 7;
+
+/// How many times an Auto Frame Play movie is repeated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Loops {
+    Endless,
+    /// Play the movie 1..=7 times, then stop on the last frame.
+    Count(u8),
+}
+
+/// Auto Frame Play configuration, written to the Configuration and Auto Play Control registers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoPlay {
+    /// First frame of the movie.
+    pub start_frame: Frame,
+    /// Number of frames in the movie (1..=8).
+    pub frame_count: u8,
+    pub loops: Loops,
+    /// Time each frame is shown, rounded to the chip's 11 ms steps (11 ms..=704 ms).
+    pub frame_delay: Duration,
+}
+
+impl AutoPlay {
+    /// Returns the Auto Play Control 1/2 register values and the frame delay they encode.
+    pub(crate) fn encode<E>(&self) -> Result<(u8, u8, Duration), Error<E>> {
+        if self.frame_count == 0 || self.frame_count > FRAME_COUNT {
+            return Err(Error::InvalidConfig);
+        }
+
+        let loops = match self.loops {
+            Loops::Endless => 0,
+            Loops::Count(count) if (1..=LOOPS_MAX).contains(&count) => count,
+            Loops::Count(_) => return Err(Error::InvalidConfig),
+        };
+
+        let units = (self.frame_delay.as_millis() + FRAME_DELAY_UNIT_MS as u128 / 2) / FRAME_DELAY_UNIT_MS as u128;
+        if units == 0 || units > FRAME_DELAY_MAX_UNITS as u128 {
+            return Err(Error::InvalidConfig);
+        }
+        let units = units as u64;
+
+        // a frame count of 8 and a delay of 64 steps are both encoded as 0
+        let control1 = (loops << 4) | (self.frame_count % FRAME_COUNT);
+        let control2 = (units % FRAME_DELAY_MAX_UNITS) as u8;
+
+        Ok((control1, control2, Duration::from_millis(units * FRAME_DELAY_UNIT_MS)))
+    }
+}
+
+/// Audio Frame Play configuration: the audio input level selects which frame of the range is shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioPlay {
+    /// First frame of the range.
+    pub start_frame: Frame,
+    /// Number of frames in the range (1..=8).
+    pub frame_count: u8,
+    /// Also modulate the LED intensity with the audio input, like [`crate::IS31FL3731::audio_sync`].
+    pub audio_sync: bool,
+}
+
+impl AudioPlay {
+    /// Returns the Auto Play Control 1 register value holding the frame count.
+    pub(crate) fn encode<E>(&self) -> Result<u8, Error<E>> {
+        if self.frame_count == 0 || self.frame_count > FRAME_COUNT {
+            return Err(Error::InvalidConfig);
+        }
+
+        Ok(self.frame_count % FRAME_COUNT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(frame_delay: Duration) -> AutoPlay {
+        AutoPlay {
+            start_frame: Frame::new(0).unwrap(),
+            frame_count: 8,
+            loops: Loops::Endless,
+            frame_delay,
+        }
+    }
+
+    #[test]
+    fn test_encode_frame_delay() {
+        assert_eq!(config(Duration::from_millis(11)).encode::<()>(), Ok((0x00, 1, Duration::from_millis(11))));
+        assert_eq!(config(Duration::from_millis(100)).encode::<()>(), Ok((0x00, 9, Duration::from_millis(99))));
+        assert_eq!(config(Duration::from_millis(704)).encode::<()>(), Ok((0x00, 0, Duration::from_millis(704))));
+        assert_eq!(config(Duration::from_millis(5)).encode::<()>(), Err(Error::InvalidConfig));
+        assert_eq!(config(Duration::from_millis(710)).encode::<()>(), Err(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn test_encode_huge_frame_delay() {
+        // 18446744073709552 s is 2^64 ms + 384 ms, which must not wrap around to a valid delay
+        assert_eq!(config(Duration::from_secs(18446744073709552)).encode::<()>(), Err(Error::InvalidConfig));
+        assert_eq!(config(Duration::MAX).encode::<()>(), Err(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn test_encode_loops_and_frames() {
+        let mut sut = config(Duration::from_millis(11));
+        sut.frame_count = 3;
+        sut.loops = Loops::Count(7);
+        assert_eq!(sut.encode::<()>(), Ok((0x73, 1, Duration::from_millis(11))));
+
+        sut.loops = Loops::Count(0);
+        assert_eq!(sut.encode::<()>(), Err(Error::InvalidConfig));
+
+        sut.loops = Loops::Endless;
+        sut.frame_count = 0;
+        assert_eq!(sut.encode::<()>(), Err(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn test_encode_audio_play() {
+        let mut sut = AudioPlay {
+            start_frame: Frame::new(1).unwrap(),
+            frame_count: 8,
+            audio_sync: false,
+        };
+        assert_eq!(sut.encode::<()>(), Ok(0));
+
+        sut.frame_count = 5;
+        assert_eq!(sut.encode::<()>(), Ok(5));
+
+        sut.frame_count = 9;
+        assert_eq!(sut.encode::<()>(), Err(Error::InvalidConfig));
+    }
+}
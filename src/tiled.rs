@@ -0,0 +1,206 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::i2c::I2c;
+
+use crate::{Error, Frame, IS31FL3731, Layout, Linear, NoPin};
+
+/// Clockwise rotation of a tile on the canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+/// One chip of a [`TiledDisplay`], placed with its top-left corner at `(x, y)` on the canvas.
+pub struct Tile<I2C, Delay, Sdb = NoPin, L = Linear>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin
+{
+    driver: IS31FL3731<I2C, Delay, Sdb, L>,
+    x: usize,
+    y: usize,
+    rotation: Rotation,
+}
+
+impl<I2C, Delay, Sdb, L> Tile<I2C, Delay, Sdb, L>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin,
+    L: Layout {
+    pub fn new(driver: IS31FL3731<I2C, Delay, Sdb, L>, x: usize, y: usize, rotation: Rotation) -> Self {
+        Self {
+            driver,
+            x,
+            y,
+            rotation,
+        }
+    }
+
+    pub fn driver(&mut self) -> &mut IS31FL3731<I2C, Delay, Sdb, L> {
+        &mut self.driver
+    }
+
+    pub fn release(self) -> IS31FL3731<I2C, Delay, Sdb, L> {
+        self.driver
+    }
+
+    /// Width and height the tile covers on the canvas.
+    fn size(&self) -> (usize, usize) {
+        let (width, height) = (self.driver.layout().width(), self.driver.layout().height());
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => (width, height),
+            Rotation::Deg90 | Rotation::Deg270 => (height, width),
+        }
+    }
+
+    /// Maps a canvas coordinate onto the tile's own layout coordinates.
+    fn to_local(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (width, height) = self.size();
+        let x = x.checked_sub(self.x).filter(|&x| x < width)?;
+        let y = y.checked_sub(self.y).filter(|&y| y < height)?;
+
+        Some(match self.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (y, width - 1 - x),
+            Rotation::Deg180 => (width - 1 - x, height - 1 - y),
+            Rotation::Deg270 => (height - 1 - y, x),
+        })
+    }
+}
+
+/// Several chips presented as one logical canvas, e.g. four 16×9 panels forming 64×9.
+pub struct TiledDisplay<I2C, Delay, const N: usize, Sdb = NoPin, L = Linear>
+where
+    I2C: I2c,
+    Delay: DelayNs,
+    Sdb: OutputPin
+{
+    tiles: [Tile<I2C, Delay, Sdb, L>; N],
+    width: usize,
+    height: usize,
+}
+
+impl<I2C, Delay, const N: usize, Sdb, L, I2cError> TiledDisplay<I2C, Delay, N, Sdb, L>
+where
+    I2C: I2c<Error = I2cError>,
+    Delay: DelayNs,
+    Sdb: OutputPin,
+    L: Layout {
+    pub fn new(tiles: [Tile<I2C, Delay, Sdb, L>; N]) -> Self {
+        let width = tiles.iter().map(|tile| tile.x + tile.size().0).max().unwrap_or(0);
+        let height = tiles.iter().map(|tile| tile.y + tile.size().1).max().unwrap_or(0);
+
+        Self {
+            tiles,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tiles(&mut self) -> &mut [Tile<I2C, Delay, Sdb, L>; N] {
+        &mut self.tiles
+    }
+
+    pub fn release(self) -> [Tile<I2C, Delay, Sdb, L>; N] {
+        self.tiles
+    }
+
+    /// Sets the pixel `(x, y)` of the canvas on whichever chip covers it.
+    pub fn set_pixel(&mut self, x: usize, y: usize, pwm: u8, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        for tile in self.tiles.iter_mut() {
+            if let Some((x, y)) = tile.to_local(x, y) {
+                return tile.driver.set_pixel(x, y, pwm, frame);
+            }
+        }
+
+        Err(Error::InvalidCoordinate)
+    }
+
+    pub fn clear(&mut self, frame: impl Into<u8>) -> Result<(), Error<I2cError>> {
+        let frame = frame.into();
+        for tile in self.tiles.iter_mut() {
+            tile.driver.clear(frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// Switches every chip to `frame`.
+    pub fn display_frame(&mut self, frame: Frame) -> Result<(), Error<I2cError>> {
+        for tile in self.tiles.iter_mut() {
+            tile.driver.display_frame(frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+    use crate::test_util::{vec, DelayStub};
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+
+    #[test]
+    fn test_side_by_side() {
+        let mut i2c = Mock::new(&[
+            Transaction::write(Address::GND as u8, vec![0xFD, 0]),
+            Transaction::write(Address::GND as u8, vec![0x24 + 15, 1]),
+            Transaction::write(Address::VCC as u8, vec![0xFD, 0]),
+            Transaction::write(Address::VCC as u8, vec![0x24 + 3 * 16 + 4, 2]),
+            Transaction::write(Address::GND as u8, vec![0xFD, 0x0B]),
+            Transaction::write(Address::GND as u8, vec![0x01, 1]),
+            Transaction::write(Address::VCC as u8, vec![0xFD, 0x0B]),
+            Transaction::write(Address::VCC as u8, vec![0x01, 1]),
+        ]);
+
+        let mut sut = TiledDisplay::new([
+            Tile::new(IS31FL3731::new(i2c.clone(), DelayStub{}, Address::GND), 0, 0, Rotation::Deg0),
+            Tile::new(IS31FL3731::new(i2c.clone(), DelayStub{}, Address::VCC), 16, 0, Rotation::Deg0),
+        ]);
+
+        assert_eq!((sut.width(), sut.height()), (32, 9));
+        sut.set_pixel(15, 0, 1, 0).unwrap();
+        sut.set_pixel(20, 3, 2, 0).unwrap();
+        assert_eq!(sut.set_pixel(32, 0, 3, 0), Err(Error::InvalidCoordinate));
+        sut.display_frame(Frame::new(1).unwrap()).unwrap();
+        i2c.done();
+    }
+
+    #[test]
+    fn test_rotated_tiles() {
+        let mut i2c = Mock::new(&[
+            // upside-down panel below the first one: canvas (0, 9) is its bottom-right LED
+            Transaction::write(Address::SCL as u8, vec![0xFD, 0]),
+            Transaction::write(Address::SCL as u8, vec![0x24 + 143, 1]),
+            // panel turned clockwise next to it: canvas (16, 0) is its bottom-left LED
+            Transaction::write(Address::SDA as u8, vec![0xFD, 0]),
+            Transaction::write(Address::SDA as u8, vec![0x24 + 128, 2]),
+        ]);
+
+        let mut sut = TiledDisplay::new([
+            Tile::new(IS31FL3731::new(i2c.clone(), DelayStub{}, Address::SCL), 0, 9, Rotation::Deg180),
+            Tile::new(IS31FL3731::new(i2c.clone(), DelayStub{}, Address::SDA), 16, 0, Rotation::Deg90),
+        ]);
+
+        assert_eq!((sut.width(), sut.height()), (25, 18));
+        sut.set_pixel(0, 9, 1, 0).unwrap();
+        sut.set_pixel(16, 0, 2, 0).unwrap();
+        i2c.done();
+    }
+}